@@ -0,0 +1,102 @@
+use std::env;
+use std::io::Write;
+use nandtetris_shared::disassembler::{self, Disassembled};
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    // --raw keeps every A-value numeric instead of recovering jump labels
+    let raw = args.iter().any(|x| x == "--raw");
+    let file_name = args.into_iter().find(|x| !x.starts_with("--")).expect("No file name provided");
+    assert!(file_name.ends_with(".hack"), "File must have .hack extension");
+    let file = std::fs::read_to_string(&file_name).expect("Could not read file");
+    let words = match disassembler::parse_hack(&file) {
+        Ok(words) => words,
+        Err(errors) => {
+            for error in &errors {
+                eprintln!("{}:{}", file_name, error);
+            }
+            std::process::exit(1);
+        }
+    };
+    let mut lines = disassembler::disassemble(&words);
+    if !raw {
+        lines = disassembler::recover_labels(lines);
+    }
+    for line in &lines {
+        if let Disassembled::Invalid { address, error } = line {
+            eprintln!("{}: warning: ROM[{}] {}", file_name, address, error);
+        }
+    }
+    let out_file = file_name.replace(".hack", ".dis.asm");
+    let file = std::fs::File::create(&out_file).expect("Could not create file");
+    let mut writer = std::io::BufWriter::new(file);
+    for line in lines {
+        writeln!(writer, "{}", line).expect("Could not write to file");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_program {
+        ($name:literal) => {
+            let input = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/", $name, ".hack"));
+            let expected = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/", $name, "L.asm"));
+            let expected = expected.lines()
+                .map(|line| line.split("//").next().unwrap().trim())
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>();
+
+            let words = disassembler::parse_hack(input).unwrap();
+            let lines = disassembler::disassemble(&words);
+            let lines = lines.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+            assert_eq!(lines, expected);
+        };
+    }
+
+    #[test]
+    fn max() {
+        test_program!("Max");
+    }
+
+    #[test]
+    fn rect() {
+        test_program!("Rect");
+    }
+
+    #[test]
+    fn pong() {
+        test_program!("Pong");
+    }
+
+    #[test]
+    fn recovers_jump_labels() {
+        let input = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/Max.hack"));
+        let words = disassembler::parse_hack(input).unwrap();
+        let lines = disassembler::recover_labels(disassembler::disassemble(&words));
+        let lines = lines.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(lines, [
+            "@0", "D=M", "@1", "D=D-M", "@L_10", "D;JGT", "@1", "D=M", "@L_12", "0;JMP",
+            "(L_10)", "@0", "D=M",
+            "(L_12)", "@2", "M=D",
+            "(L_14)", "@L_14", "0;JMP",
+        ]);
+    }
+
+    #[test]
+    fn flags_unknown_comp() {
+        let words = disassembler::parse_hack("0000000000000101\n1110101011010000\n").unwrap();
+        let lines = disassembler::disassemble(&words);
+        let lines = lines.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(lines, ["@5", "// ROM[1] 1110101011010000: unknown comp bits 0101011"]);
+
+        let words = disassembler::parse_hack("1000000000000000\n1010000000000000\n").unwrap();
+        let lines = disassembler::disassemble(&words);
+        let lines = lines.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(lines, [
+            "// ROM[0] 1000000000000000: C-instruction does not start with 111",
+            "// ROM[1] 1010000000000000: C-instruction does not start with 111",
+        ]);
+    }
+}
@@ -0,0 +1,183 @@
+use std::env;
+use nandtetris_shared::assembler::OutputFormat;
+use nandtetris_shared::object::{self, Object};
+
+const USAGE: &str = "Usage: hack-ld [--format <format>] [-o <output>] [--symbols] <file.hobj>...";
+
+#[derive(Debug, Default)]
+struct Args {
+    /// Modules in ROM order. Output names are derived from the first one.
+    file_names: Vec<String>,
+    output: Option<String>,
+    format: OutputFormat,
+    symbols: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut result = Args::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-f" | "--format" => {
+                    let format = args.next().ok_or("--format expects a value")?;
+                    result.format = format.parse()?;
+                }
+                "-o" | "--output" => {
+                    result.output = Some(args.next().ok_or("--output expects a value")?);
+                }
+                "--symbols" => result.symbols = true,
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ if arg.ends_with(".hobj") => result.file_names.push(arg),
+                _ => return Err(format!("File must have .hobj extension: {}", arg)),
+            }
+        }
+        if result.file_names.is_empty() {
+            return Err("No file name provided".to_string());
+        }
+        Ok(result)
+    }
+
+    fn output_file(&self) -> String {
+        self.output.clone().unwrap_or_else(|| self.sibling_file(self.format.extension()))
+    }
+
+    /// First input file name with `.hobj` replaced by `extension`.
+    fn sibling_file(&self, extension: &str) -> String {
+        let stem = self.file_names[0].strip_suffix(".hobj").unwrap_or(&self.file_names[0]);
+        format!("{}.{}", stem, extension)
+    }
+}
+
+fn main() {
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        std::process::exit(2);
+    });
+    let file_names = &args.file_names;
+    let objects = file_names.iter()
+        .map(|x| {
+            let content = std::fs::read_to_string(x).unwrap_or_else(|e| panic!("Could not read file {}: {}", x, e));
+            Object::read(&content).unwrap_or_else(|e| {
+                eprintln!("{}:{}", x, e);
+                std::process::exit(1);
+            })
+        })
+        .collect::<Vec<_>>();
+    let modules = file_names.iter().map(String::as_str).zip(&objects).collect::<Vec<_>>();
+    let (instructions, symbol_table) = object::link(&modules).unwrap_or_else(|errors| {
+        for error in &errors {
+            eprintln!("{}", error);
+        }
+        eprintln!("{} error(s), no output written", errors.len());
+        std::process::exit(1);
+    });
+
+    if args.symbols {
+        let file = std::fs::File::create(args.sibling_file("sym")).expect("Could not create symbol file");
+        symbol_table.write_sym(&mut std::io::BufWriter::new(file)).expect("Could not write symbol file");
+    }
+    let file = std::fs::File::create(args.output_file()).expect("Could not create file");
+    args.format.write(&instructions, &mut std::io::BufWriter::new(file)).expect("Could not write to file");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nandtetris_shared::assembler::{Context, Source};
+
+    const MAIN: &str = "\
+@5
+D=A
+@count
+M=D
+(LOOP)
+@DOUBLE
+0;JMP
+(BACK)
+@count
+MD=M-1
+@LOOP
+D;JGT
+(END)
+@END
+0;JMP
+";
+
+    const DOUBLE: &str = "\
+(DOUBLE)
+@total
+D=M
+M=D+M
+@BACK
+0;JMP
+(TABLE)
+@TABLE+1
+";
+
+    fn object(content: &str) -> Object {
+        let object = Context::default().assemble_object(&[Source::new(content)]).unwrap();
+        let mut written = Vec::new();
+        object.write(&mut written).unwrap();
+        let read = Object::read(&String::from_utf8(written).unwrap()).unwrap();
+        assert_eq!(read, object);
+        read
+    }
+
+    #[test]
+    fn parses_args() {
+        let args = ["--format", "ihex", "--symbols", "main.hobj", "lib.hobj"].map(String::from);
+        let args = Args::parse(args.into_iter()).unwrap();
+        assert_eq!(args.format, OutputFormat::IntelHex);
+        assert_eq!(args.file_names, ["main.hobj", "lib.hobj"]);
+        assert!(args.symbols);
+        assert_eq!(args.output_file(), "main.hex");
+        assert_eq!(args.sibling_file("sym"), "main.sym");
+
+        let args = ["-o", "out.hack", "main.hobj"].map(String::from);
+        assert_eq!(Args::parse(args.into_iter()).unwrap().output_file(), "out.hack");
+
+        let args = ["-f", "nope", "main.hobj"].map(String::from);
+        assert!(Args::parse(args.into_iter()).unwrap_err().starts_with("Unknown output format nope"));
+        let args = ["main.asm"].map(String::from);
+        assert_eq!(Args::parse(args.into_iter()).unwrap_err(), "File must have .hobj extension: main.asm");
+        let args = ["--symbols"].map(String::from);
+        assert_eq!(Args::parse(args.into_iter()).unwrap_err(), "No file name provided");
+        let args = ["-o"].map(String::from);
+        assert_eq!(Args::parse(args.into_iter()).unwrap_err(), "--output expects a value");
+    }
+
+    #[test]
+    fn links_like_one_program() {
+        let main = object(MAIN);
+        let double = object(DOUBLE);
+        assert_eq!(double.imports, ["total", "BACK"]);
+        assert_eq!(double.exports, [("DOUBLE".to_string(), 0), ("TABLE".to_string(), 5)]);
+
+        let (linked, symbol_table) = object::link(&[("main", &main), ("double", &double)]).unwrap();
+        let expected = Context::default().assemble_sources(&[Source::new(MAIN), Source::new(DOUBLE)]).unwrap();
+        assert_eq!(linked, expected);
+        assert_eq!(symbol_table.get("TABLE"), Some(17));
+        assert_eq!(symbol_table.get("total"), Some(17));
+    }
+
+    #[test]
+    fn reports_link_errors() {
+        let main = object(MAIN);
+        let errors = object::link(&[("a", &main), ("b", &main)]).unwrap_err();
+        let errors = errors.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "label `LOOP` is exported by both a and b",
+            "label `BACK` is exported by both a and b",
+            "label `END` is exported by both a and b",
+        ]);
+
+        let errors = Context::default().assemble_object(&[Source::new("@x+y\n@x-1\n(L)\n@L+L\n.data BUF 2\n")]).unwrap_err();
+        let errors = errors.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "1:1: `x+y` cannot be relocated, use a constant plus at most one label or external symbol",
+            "2:1: `x-1` cannot be relocated, negative offsets from external symbols are not supported",
+            "4:1: `L+L` cannot be relocated, use a constant plus at most one label or external symbol",
+            "5:1: data directives are not supported in relocatable objects",
+        ]);
+    }
+}
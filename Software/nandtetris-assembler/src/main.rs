@@ -1,142 +1,187 @@
-use core::fmt;
-use std::borrow::Cow;
-use std::{env, fmt::Display};
-use std::io::Write;
-use nandtetris_shared::assembler::{Address, CodeLine, Comp, Dest, Jump, PREDEFINED_SYMBOLS};
-
-#[derive(Debug)]
-enum Command {
-    A(u16),
-    C {
-        comp: Comp,
-        dest: Dest,
-        jump: Jump,
-    }
-}
+use std::env;
+use nandtetris_shared::assembler::{
+    is_symbol, parse_literal, Context, Errors, OutputFormat, Severity, Source, SymbolKind, Usage,
+};
 
-struct Instruction(u16);
+const USAGE: &str = "Usage: nandtetris-assembler [--format <format>] [-o <output>] [-I <dir>]... \
+[-D <name>[=<value>]]... [--optimize] [--dead-code] [--strip-dead-code] [--lint] [--ram-overflow <warning|error>] [--usage] [--listing] [--symbol-less] [--symbols] [--symbols-json] [-c] <file.asm>...";
 
-impl Display for Instruction {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:016b}", self.0)
-    }
+#[derive(Debug, Default)]
+struct Args {
+    /// Input files, assembled as one program. Output names are derived from the first one.
+    file_names: Vec<String>,
+    include_paths: Vec<String>,
+    /// Constants defined before assembling, for `.if` and `.ifdef`.
+    defines: Vec<(String, u16)>,
+    output: Option<String>,
+    format: OutputFormat,
+    optimize: bool,
+    dead_code: bool,
+    strip_dead_code: bool,
+    lint: bool,
+    ram_overflow: Severity,
+    usage: bool,
+    listing: bool,
+    /// Also writes `<name>L.asm` with numeric A-instructions only.
+    symbol_less: bool,
+    symbols: bool,
+    symbols_json: bool,
+    /// Writes a relocatable object for `hack-ld` instead of a program.
+    object: bool,
 }
 
-impl From<&Command> for Instruction {
-    fn from(value: &Command) -> Self {
-        match value {
-            Command::A(val) => Instruction(*val),
-            Command::C { comp, dest, jump } => {
-                let mut instruction = 0b111;
-                instruction = instruction << 7 | u16::from(comp);
-                instruction = instruction << 3 | u16::from(dest);
-                instruction = instruction << 3 | u16::from(jump);
-                Instruction(instruction)
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut result = Args::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-f" | "--format" => {
+                    let format = args.next().ok_or("--format expects a value")?;
+                    result.format = format.parse()?;
+                }
+                "-o" | "--output" => {
+                    result.output = Some(args.next().ok_or("--output expects a value")?);
+                }
+                "-I" | "--include" => {
+                    result.include_paths.push(args.next().ok_or("--include expects a directory")?);
+                }
+                "-D" | "--define" => {
+                    let define = args.next().ok_or("--define expects a name")?;
+                    result.defines.push(parse_define(&define)?);
+                }
+                "-O" | "--optimize" => result.optimize = true,
+                "--dead-code" => result.dead_code = true,
+                "--strip-dead-code" => result.strip_dead_code = true,
+                "--lint" => result.lint = true,
+                "--ram-overflow" => {
+                    let severity = args.next().ok_or("--ram-overflow expects warning or error")?;
+                    result.ram_overflow = severity.parse()?;
+                }
+                "--usage" => result.usage = true,
+                "-l" | "--listing" => result.listing = true,
+                "--symbol-less" => result.symbol_less = true,
+                "--symbols" => result.symbols = true,
+                "--symbols-json" => result.symbols_json = true,
+                "-c" | "--object" => result.object = true,
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ if arg.ends_with(".asm") => result.file_names.push(arg),
+                _ => return Err(format!("File must have .asm extension: {}", arg)),
             }
         }
+        if result.file_names.is_empty() {
+            return Err("No file name provided".to_string());
+        }
+        if result.symbol_less && (result.listing || result.object) {
+            return Err("--symbol-less cannot be combined with --listing or --object".to_string());
+        }
+        Ok(result)
     }
-}
 
-#[derive(Debug)]
-struct SymbolTable {
-    symbols: std::collections::HashMap<Cow<'static, str>, u16>,
-    next_address: u16,
-}
+    fn output_file(&self) -> String {
+        let extension = if self.object { "hobj" } else { self.format.extension() };
+        self.output.clone().unwrap_or_else(|| self.sibling_file(extension))
+    }
 
-impl SymbolTable {
-    fn get_or_insert(&mut self, variable: Cow<'static, str>) -> u16 {
-        *self.symbols.entry(variable).or_insert_with(|| {
-            let address = self.next_address;
-            self.next_address += 1;
-            address
-        })
+    /// Input file name with `.asm` replaced by `extension`.
+    fn sibling_file(&self, extension: &str) -> String {
+        let stem = self.file_names[0].strip_suffix(".asm").unwrap_or(&self.file_names[0]);
+        format!("{}.{}", stem, extension)
     }
 
-    fn insert(&mut self, symbol: Cow<'static, str>, address: u16) {
-        // dbg!(&self, &symbol, &address);
-        if let Some(x) = self.symbols.insert(symbol.clone(), address) {
-            panic!("Symbol {} already exists with address {}", symbol, x);
-        }
+    /// Input file name with `.asm` replaced by `L.asm`, as in the nand2tetris projects.
+    fn symbol_less_file(&self) -> String {
+        let stem = self.file_names[0].strip_suffix(".asm").unwrap_or(&self.file_names[0]);
+        format!("{}L.asm", stem)
     }
 }
 
-impl Default for SymbolTable {
-    fn default() -> Self {
-        SymbolTable {
-            symbols: PREDEFINED_SYMBOLS.iter().map(|x| (x.name.into(), x.value)).collect(),
-            next_address: 16,
-        }
+/// Parses `NAME` or `NAME=value`, the value defaults to 1.
+fn parse_define(define: &str) -> Result<(String, u16), String> {
+    let (name, value) = define.split_once('=').unwrap_or((define, "1"));
+    if !is_symbol(name) {
+        return Err(format!("Invalid symbol name {}", name));
     }
+    let value = parse_literal(value)
+        .and_then(|x| u16::try_from(x).ok())
+        .ok_or_else(|| format!("Invalid value for {}: {}", name, value))?;
+    Ok((name.to_string(), value))
 }
 
-#[derive(Debug, Default)]
-struct Context {
-    symbol_table: SymbolTable,
+fn report_errors(errors: &Errors) -> ! {
+    for error in errors {
+        eprintln!("{}", error);
+    }
+    eprintln!("{} error(s), no output written", errors.len());
+    std::process::exit(1);
 }
 
-impl Context {
-    fn assemble(&mut self, content: &str) -> Vec<Instruction> {
-        let commands = self.parse_file(content);
-        // dbg!(&commands);
-        commands.iter().map(|c| Instruction::from(c)).collect()
+fn main() {
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        std::process::exit(2);
+    });
+    let sources = args.file_names.iter()
+        .map(|x| Source::read(x).unwrap_or_else(|e| panic!("Could not read file {}: {}", x, e)))
+        .collect::<Vec<_>>();
+    let mut context = Context::default();
+    context.include_paths = args.include_paths.iter().map(Into::into).collect();
+    context.optimize = args.optimize;
+    context.dead_code = args.dead_code;
+    context.strip_dead_code = args.strip_dead_code;
+    context.lint = args.lint;
+    context.ram_overflow = args.ram_overflow;
+    for (name, value) in &args.defines {
+        if context.symbol_table.insert(name.clone().into(), *value, SymbolKind::Constant).is_err() {
+            eprintln!("Symbol {} is already defined\n{}", name, USAGE);
+            std::process::exit(2);
+        }
     }
-
-    fn parse_file(&mut self, content: &str) -> Vec<Command> {
-        let mut code_lines = content.lines()
-            .map(|line| {
-                if let Some(comment_idx) = line.find("//") {
-                    line[..comment_idx].trim()
-                } else {
-                    line.trim()
-                }
-            })
-            .filter(|x| !x.is_empty())
-            .map(CodeLine::from_str)
-
-            .collect::<Vec<_>>()
-            ;
-        let mut line_number = 0;
-        for line in code_lines.iter_mut() {
-            match line {
-                CodeLine::Label(ref mut label) => {
-                    self.symbol_table.insert(std::mem::take(label).into(), line_number as u16);
-                }
-                _ => {
-                    line_number += 1;
-                }
-            }
+    if args.object {
+        let result = context.assemble_object(&sources);
+        for warning in &context.warnings {
+            eprintln!("{}", warning);
         }
-
-        code_lines.into_iter().filter_map(|line| {
-            match line {
-                CodeLine::A(Address::Variable(symbol)) => {
-                    let address = self.symbol_table.get_or_insert(symbol);
-                    Some(Command::A(address))
-                }
-                CodeLine::A(Address::Value(address)) => {
-                    Some(Command::A(address))
-                }
-                CodeLine::C { comp, dest, jump } => {
-                    Some(Command::C { comp, dest, jump })
-                }
-                _ => None
-            }
-        }).collect()
-
+        let object = result.unwrap_or_else(|errors| report_errors(&errors));
+        let file = std::fs::File::create(args.output_file()).expect("Could not create file");
+        object.write(&mut std::io::BufWriter::new(file)).expect("Could not write to file");
+        return;
     }
-}
-
-fn main() {
-    let file_name = env::args().nth(1).expect("No file name provided");
-    assert!(file_name.ends_with(".asm"), "File must have .asm extension");
-    let file = std::fs::read_to_string(&file_name).expect("Could not read file");
-    let instructions = Context::default().assemble(&file);
-    let out_file = file_name.replace(".asm", ".hack");
-    let file = std::fs::File::create(&out_file).expect("Could not create file");
-    let mut writer = std::io::BufWriter::new(file);
-    for instruction in instructions {
-        writeln!(writer, "{}", instruction).expect("Could not write to file");
+    let result = if args.listing {
+        context.assemble_sources_listing(&sources).map(|listing| {
+            std::fs::write(args.sibling_file("lst"), listing.to_string()).expect("Could not write listing");
+            listing.instructions().collect()
+        })
+    } else if args.symbol_less {
+        context.assemble_symbol_less(&sources).map(|(text, instructions)| {
+            std::fs::write(args.symbol_less_file(), text).expect("Could not write symbol-less file");
+            instructions
+        })
+    } else {
+        context.assemble_sources(&sources)
+    };
+    for warning in &context.warnings {
+        eprintln!("{}", warning);
+    }
+    let instructions: Vec<_> = result.unwrap_or_else(|errors| report_errors(&errors));
+    if args.usage {
+        println!("{}", Usage::new(instructions.len(), &context.symbol_table));
+    }
+    if args.symbols {
+        let file = std::fs::File::create(args.sibling_file("sym")).expect("Could not create symbol file");
+        context.symbol_table.write_sym(&mut std::io::BufWriter::new(file)).expect("Could not write symbol file");
     }
+    if args.symbols_json {
+        let file = std::fs::File::create(args.sibling_file("sym.json")).expect("Could not create symbol file");
+        context.symbol_table.write_json(&mut std::io::BufWriter::new(file)).expect("Could not write symbol file");
+    }
+    if !context.ram_image.is_empty() {
+        let file = std::fs::File::create(args.sibling_file(&format!("ram.{}", args.format.extension())))
+            .expect("Could not create RAM image");
+        args.format.write_ram(&context.ram_image, &mut std::io::BufWriter::new(file)).expect("Could not write RAM image");
+    }
+    let file = std::fs::File::create(args.output_file()).expect("Could not create file");
+    let mut writer = std::io::BufWriter::new(file);
+    args.format.write(&instructions, &mut writer).expect("Could not write to file");
 }
 
 #[cfg(test)]
@@ -163,7 +208,7 @@ mod tests {
             let (input, expected) = get_test_files!($name, $l);
             let expected = expected.lines().collect::<Vec<_>>();
 
-            let instructions = Context::default().assemble(input);
+            let instructions = Context::default().assemble(input).unwrap();
             let instructions = instructions.iter().map(|x| x.to_string()).collect::<Vec<_>>();
             assert_eq!(instructions, expected);
         };
@@ -213,4 +258,326 @@ mod tests {
     fn mult() {
         test_program!("Mult", "");
     }
+
+    macro_rules! test_symbol_less {
+        ($name:literal) => {
+            let (input, _) = get_test_files!($name, "");
+            let expected = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/", $name, "L.asm"));
+            let code = |text: &str| text.lines()
+                .map(|line| line.split("//").next().unwrap().trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>();
+
+            let (text, instructions) = Context::default().assemble_symbol_less(&[Source::new(input)]).unwrap();
+            assert_eq!(code(&text), code(expected));
+            assert_eq!(instructions, Context::default().assemble(input).unwrap());
+            assert_eq!(instructions, Context::default().assemble(&text).unwrap());
+        };
+    }
+
+    #[test]
+    fn symbol_less() {
+        test_symbol_less!("Max");
+        test_symbol_less!("Rect");
+        test_symbol_less!("Pong");
+
+        let input = ".equ ROWS 256\n.word TABLE 1, 2\n@ROWS\nD=A\n(LOOP)\n@TABLE+1\nM=D\n@LOOP\n0;JMP\n";
+        let (text, _) = Context::default().assemble_symbol_less(&[Source::new(input)]).unwrap();
+        assert_eq!(text, "// ROWS = 256\n// TABLE = 16\n@256 // ROWS\nD=A\n// (LOOP)\n@17 // TABLE+1\nM=D\n@2 // LOOP\n0;JMP\n");
+    }
+
+    #[test]
+    fn parses_args() {
+        let args = ["--format", "ihex", "Max.asm"].map(String::from);
+        let args = Args::parse(args.into_iter()).unwrap();
+        assert_eq!(args.format, OutputFormat::IntelHex);
+        assert_eq!(args.output_file(), "Max.hex");
+
+        let args = ["-D", "DEBUG", "-D", "LEVEL=0x10", "Max.asm"].map(String::from);
+        let args = Args::parse(args.into_iter()).unwrap();
+        assert_eq!(args.defines, [("DEBUG".to_string(), 1), ("LEVEL".to_string(), 16)]);
+
+        let args = ["-f", "nope", "Max.asm"].map(String::from);
+        assert!(Args::parse(args.into_iter()).unwrap_err().starts_with("Unknown output format nope"));
+
+        let error = "--symbol-less cannot be combined with --listing or --object";
+        let args = ["--symbol-less", "--listing", "Max.asm"].map(String::from);
+        assert_eq!(Args::parse(args.into_iter()).unwrap_err(), error);
+        let args = ["-c", "--symbol-less", "Max.asm"].map(String::from);
+        assert_eq!(Args::parse(args.into_iter()).unwrap_err(), error);
+    }
+
+    #[test]
+    fn reports_every_error() {
+        let input = "@R0\nX=M\nD=Q // comment\n(LOOP\n  @99999\n0;JPM\n@1abc\n";
+        let errors = Context::default().assemble(input).err().unwrap();
+        let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "2:1: illegal dest letter `X`",
+            "3:3: unknown comp `Q`",
+            "4:1: unterminated label, expected `)`",
+            "5:4: A-value `99999` does not fit in 15 bits",
+            "6:3: unknown jump `JPM`",
+            "7:2: invalid number `1abc`",
+        ]);
+    }
+
+    #[test]
+    fn numeric_literals() {
+        let input = "@0x4000\n@0b101\n@'A'\n@32767\n";
+        let instructions = Context::default().assemble(input).unwrap();
+        let instructions = instructions.into_iter().map(u16::from).collect::<Vec<_>>();
+        assert_eq!(instructions, [0x4000, 0b101, 65, 32767]);
+        assert!(Context::default().symbol_table.get("0x4000").is_none());
+
+        let errors = Context::default().assemble("@-1\n@0x8000\n@'AB'\n@0x10000\n").err().unwrap();
+        let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "1:2: A-value `-1` does not fit in 15 bits, load it with `@0` followed by `A=!A`",
+            "2:2: A-value `0x8000` does not fit in 15 bits, load it with `@32767` followed by `A=!A`",
+            "3:2: invalid number `'AB'`",
+            "4:2: A-value `0x10000` does not fit in 15 bits",
+        ]);
+    }
+
+    #[test]
+    fn reports_duplicate_label() {
+        let errors = Context::default().assemble("(LOOP)\n@LOOP\n  (LOOP) // again\n").err().unwrap();
+        assert_eq!(errors.to_string(), "3:3: label `LOOP` is already defined at line 1 with address 0");
+    }
+
+    #[test]
+    fn constants_and_expressions() {
+        let input = ".equ ROWS 256\n.equ LAST, ROWS-1\n@SCREEN+32\n@TABLE+3\n(TABLE)\n@LAST\n@END-1\n(END)\n";
+        let instructions = Context::default().assemble(input).unwrap();
+        let instructions = instructions.into_iter().map(u16::from).collect::<Vec<_>>();
+        assert_eq!(instructions, [0x4020, 5, 255, 3]);
+
+        let input = ".equ BIG 0xFFFF\n@BIG\n@nope+1\n@SCREEN-0x4001\n.equ BIG 1\n.equ X Y\n";
+        let errors = Context::default().assemble(input).err().unwrap();
+        let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "2:1: A-value `BIG` does not fit in 15 bits",
+            "3:1: undefined symbol `nope` in expression",
+            "4:1: A-value `SCREEN-16385` does not fit in 15 bits",
+            "5:1: symbol `BIG` is already defined",
+            "6:1: undefined symbol `Y` in expression",
+        ]);
+
+        let input = "@9223372036854775807+9223372036854775807\n@0-9223372036854775807-9\n";
+        let errors = Context::default().assemble(input).err().unwrap();
+        let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "1:1: expression `9223372036854775807+9223372036854775807` overflows",
+            "2:1: expression `0-9223372036854775807-9` overflows",
+        ]);
+        let errors = Context::default().assemble(".rept 0x7fffffffffffffff+1\n.endr\n").err().unwrap();
+        assert_eq!(errors.to_string(), "1:1: expression `9223372036854775807+1` overflows");
+
+        // errors follow the order of the sources, not of the file names
+        let sources = [
+            Source { name: Some("b.asm".into()), content: "D=M\n@x+y\n".to_string() },
+            Source { name: Some("a.asm".into()), content: ".equ X nope\n".to_string() },
+        ];
+        let errors = Context::default().assemble_sources(&sources).err().unwrap();
+        let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "b.asm:2:1: undefined symbol `x` in expression",
+            "a.asm:1:1: undefined symbol `nope` in expression",
+        ]);
+    }
+
+    #[test]
+    fn data_directives() {
+        let input = "\
+.word TABLE 1, -1, END
+.string HELLO \"Hi\\n\"
+.data BUFFER 2
+.equ AFTER BUFFER+2
+@TABLE
+@HELLO+1
+@AFTER
+@x
+(END)
+";
+        let mut context = Context::default();
+        let instructions = context.assemble(input).unwrap();
+        let instructions = instructions.into_iter().map(u16::from).collect::<Vec<_>>();
+        assert_eq!(instructions, [16, 20, 25, 25]);
+        let mut ram = vec![0; 16];
+        ram.extend([1, 0xFFFF, 4, 'H' as u16, 'i' as u16, '\n' as u16, 0, 0, 0]);
+        assert_eq!(context.ram_image, ram);
+
+        let errors = Context::default().assemble(".word A 0x10000\n.string B Hi\n.data C 1\n.word C 1\n").err().unwrap();
+        let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "2:1: invalid string Hi, expected `\"text\"`",
+        ]);
+        let errors = Context::default().assemble(".word A 0x10000\n.data C 1\n.word C 1\n").err().unwrap();
+        let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "1:1: value `65536` does not fit in 16 bits",
+            "3:1: symbol `C` is already defined",
+        ]);
+    }
+
+    #[test]
+    fn dead_code() {
+        let input = "\
+@R0
+D=M
+@END
+D;JLE
+(UNUSED)
+D=D-1
+@END
+0;JMP
+(SKIPPED)
+M=0
+@SKIPPED
+0;JMP
+(END)
+@END
+0;JMP
+";
+        let mut context = Context::default();
+        context.dead_code = true;
+        let plain = context.assemble(input).unwrap();
+        let warnings = context.warnings.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(warnings, [
+            "5:1: warning: label `UNUSED` is never used",
+            "10:1: warning: 3 unreachable instruction(s)",
+        ]);
+
+        let mut context = Context::default();
+        context.strip_dead_code = true;
+        let stripped = context.assemble(input).unwrap();
+        assert_eq!(stripped.len(), plain.len() - 3);
+        let expected = "@R0\nD=M\n@END\nD;JLE\nD=D-1\n@END\n0;JMP\n(END)\n@END\n0;JMP\n";
+        assert_eq!(stripped, Context::default().assemble(expected).unwrap());
+    }
+
+    #[test]
+    fn keeps_jump_tables() {
+        // R1 = R0 through a table of jumps, the entries after the first are only reached by offset
+        let input = "\
+@R0
+D=M
+@TABLE
+A=D+A
+0;JMP
+(TABLE)
+@ZERO
+0;JMP
+@ONE
+0;JMP
+(ZERO)
+@R1
+M=0
+@END
+0;JMP
+(ONE)
+@R1
+M=1
+(END)
+@END
+0;JMP
+(UNUSED)
+D=1
+";
+        let mut context = Context::default();
+        context.strip_dead_code = true;
+        let stripped = context.assemble(input).unwrap();
+        let warnings = context.warnings.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(warnings, ["22:1: warning: label `UNUSED` is never used", "23:1: warning: 1 unreachable instruction(s)"]);
+        let expected = input.strip_suffix("(UNUSED)\nD=1\n").unwrap();
+        assert_eq!(stripped, Context::default().assemble(expected).unwrap());
+    }
+
+    #[test]
+    fn keeps_layout_relative_code() {
+        let optimize = |input| {
+            let mut context = Context::default();
+            context.optimize = true;
+            context.assemble(input).unwrap()
+        };
+        // `@0` is a dead load, removing it would move the code `@TABLE+1` jumps to
+        let table = "@TABLE+1\n0;JMP\n(TABLE)\n@0\n@5\nD=A\n@R0\nM=D\n(END)\n@END\n0;JMP\n";
+        assert_eq!(optimize(table), Context::default().assemble(table).unwrap());
+        let numeric = "@3\n0;JMP\n@0\n@5\nD=A\n@R0\nM=D\n(END)\n@END\n0;JMP\n";
+        assert_eq!(optimize(numeric), Context::default().assemble(numeric).unwrap());
+        let plain = "@0\n@5\nD=A\n@R0\nM=D\n";
+        assert_eq!(optimize(plain).len(), 4);
+
+        let input = "@TABLE+2\n0;JMP\n(TABLE)\n@END\n0;JMP\n@7\nD=A\n@R0\nM=D\n(END)\n@END\n0;JMP\n";
+        let mut context = Context::default();
+        context.strip_dead_code = true;
+        assert_eq!(context.assemble(input).unwrap(), Context::default().assemble(input).unwrap());
+    }
+
+    #[test]
+    fn lint() {
+        let input = "\
+@counter
+M=0
+(LOOP)
+@counter
+M=M+1;JGT
+D-1;JEQ
+@loop
+M=1
+@countr
+D=M
+@LOOP
+0;JMP
+(DONE)
+";
+        let mut context = Context::default();
+        context.lint = true;
+        context.assemble(input).unwrap();
+        let warnings = context.warnings.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(warnings, [
+            "5:1: warning: `M=M+1;JGT` accesses M and jumps, both use the address in A",
+            "6:1: warning: jump tests `D-1` but the result is not stored",
+            "7:1: warning: variable `loop` differs from `LOOP` only by case",
+            "7:1: warning: variable `loop` is used only once",
+            "9:1: warning: variable `countr` is used only once",
+            "13:1: warning: label `DONE` is never used",
+        ]);
+    }
+
+    #[test]
+    fn capacity_checks() {
+        let errors = Context::default().assemble(".rept 0x8000\nD=0\n.endr\n\nD=1\nD=-1\n").err().unwrap();
+        assert_eq!(errors.to_string(), "5:1: program needs 32770 instructions, the ROM only holds 32768");
+
+        let input = ".data BUFFER 16366\n.word TABLE 1, 2, 3\n@x\nM=0\n";
+        let mut context = Context::default();
+        let instructions = context.assemble(input).unwrap();
+        let warnings = context.warnings.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(warnings, [
+            "2:1: warning: `TABLE` at RAM[16382..=16384] overlaps SCREEN memory starting at 16384",
+            "3:1: warning: `x` at RAM[16385] overlaps SCREEN memory starting at 16384",
+        ]);
+        let usage = Usage::new(instructions.len(), &context.symbol_table);
+        assert_eq!(usage, Usage { rom: 2, ram: 16370 });
+        assert_eq!(usage.to_string(), "ROM: 2 of 32768 words (0.0%)\nRAM: 16370 of 16368 words for variables and data (100.0%)");
+
+        let mut context = Context::default();
+        context.ram_overflow = Severity::Error;
+        let errors = context.assemble(".data BUFFER 0x2000\n.data SCREEN_COPY 0x2001\n").err().unwrap();
+        assert_eq!(errors.to_string(), "2:1: `SCREEN_COPY` at RAM[8208..=16400] overlaps SCREEN memory starting at 16384");
+
+        // running out of RAM is an error even if overflows are warnings
+        let errors = Context::default().assemble(".data A1 32767\n.data A2 32767\n@x\n@y\n").err().unwrap();
+        let errors = errors.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "2:1: `A2` at RAM[32783..=65549] reaches past the end of RAM",
+            "3:1: A-value `x` does not fit in 15 bits",
+            "4:1: A-value `y` does not fit in 15 bits",
+        ]);
+        let errors = Context::default().assemble(".data A1 32767\n.data A2 32753\n@x\n").err().unwrap();
+        let errors = errors.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, ["3:1: `x` at RAM[65536] reaches past the end of RAM"]);
+    }
 }
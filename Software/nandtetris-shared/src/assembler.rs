@@ -1,6 +1,95 @@
-use std::{borrow::Cow, str::FromStr};
+use std::{borrow::Cow, ops::Range, str::FromStr};
+
+mod context;
+mod decode;
+mod error;
+mod expression;
+mod instruction;
+mod lint;
+mod listing;
+mod output;
+mod preprocess;
+mod pseudo;
+mod statement;
+mod symbol_table;
+mod usage;
+mod warning;
+
+pub use context::{assemble, Context};
+pub use decode::DecodeError;
+pub use error::{Error, ErrorKind, Errors, Location, LocationKind, RamOverflow};
+pub use expression::{Expression, Term};
+pub use instruction::{Command, Instruction};
+pub use listing::{Listing, ListingRow};
+pub use output::OutputFormat;
+pub use preprocess::Source;
+pub use symbol_table::{AllocationError, Symbol, SymbolKind, SymbolTable};
+pub use usage::Usage;
+pub use warning::{Severity, Warning, WarningKind};
+
+/// Largest value an A-instruction can load, the top bit selects C-instructions.
+pub const MAX_A_VALUE: u16 = 0x7FFF;
+
+/// Number of instructions the ROM holds.
+pub const ROM_SIZE: usize = 0x8000;
+
+/// Drops the comment and surrounding whitespace of a source line.
+/// Returns the remaining code together with its byte offset in `line`.
+pub(crate) fn strip_comment(line: &str) -> (usize, &str) {
+    let code = match line.find("//") {
+        Some(comment_idx) => &line[..comment_idx],
+        None => line,
+    };
+    let offset = code.len() - code.trim_start().len();
+    (offset, code.trim())
+}
+
+/// Whether `s` should be read as a number rather than a symbol.
+pub fn is_literal(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '\'')
+}
+
+/// Parses a numeric literal: decimal, `0x` hex, `0b` binary or a `'c'` ASCII character,
+/// optionally negated with `-`. Returns `None` for malformed literals, range checks are left to the caller.
+pub fn parse_literal(s: &str) -> Option<i64> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let value = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(bin) = s.strip_prefix("0b").or_else(|| s.strip_prefix("0B")) {
+        i64::from_str_radix(bin, 2).ok()?
+    } else if let Some(c) = s.strip_prefix('\'').and_then(|x| x.strip_suffix('\'')) {
+        let mut chars = c.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii() => c as i64,
+            _ => return None,
+        }
+    } else if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse::<i64>().ok()?
+    } else {
+        return None;
+    };
+    // a second sign, e.g. `--1`, is not a literal
+    if value < 0 {
+        return None;
+    }
+    Some(if negative { -value } else { value })
+}
+
+/// Checks the symbol syntax of the Hack spec: letters, digits, `_`, `.`, `$` and `:`,
+/// not starting with a digit.
+pub fn is_symbol(s: &str) -> bool {
+    let mut chars = s.chars();
+    let is_symbol_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '$' | ':');
+    match chars.next() {
+        Some(first) => !first.is_ascii_digit() && is_symbol_char(first) && chars.all(is_symbol_char),
+        None => false,
+    }
+}
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Dest {
     pub a: bool,
     pub m: bool,
@@ -12,6 +101,7 @@ impl Dest {
     pub const M: Dest = Dest { a: false, m: true, d: false };
     pub const D: Dest = Dest { a: false, m: false, d: true };
 
+    pub const AM: Dest = Dest { a: true, m: true, d: false };
     pub const AD: Dest = Dest { a: true, m: false, d: true };
 }
 
@@ -40,7 +130,7 @@ impl std::fmt::Display for Dest {
 }
 
 #[repr(u16)]
-#[derive(Debug, Clone, Copy, derive_more::FromStr)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Jump {
     Null = 0b000,
     JGT = 0b001,
@@ -58,6 +148,23 @@ impl From<&Jump> for u16 {
     }
 }
 
+impl FromStr for Jump {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "JGT" => Ok(Jump::JGT),
+            "JEQ" => Ok(Jump::JEQ),
+            "JGE" => Ok(Jump::JGE),
+            "JLT" => Ok(Jump::JLT),
+            "JNE" => Ok(Jump::JNE),
+            "JLE" => Ok(Jump::JLE),
+            "JMP" => Ok(Jump::JMP),
+            _ => Err("Invalid Jump string"),
+        }
+    }
+}
+
 impl std::fmt::Display for Jump {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let s = match self {
@@ -75,7 +182,7 @@ impl std::fmt::Display for Jump {
 }
 
 #[repr(u16)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Comp {
     // a=0
     Zero = 0b0101010,
@@ -226,18 +333,63 @@ impl From<&Comp> for &'static str {
 }
 
 impl Comp {
+    pub const ALL: [Comp; 28] = [
+        Comp::Zero,
+        Comp::One,
+        Comp::NegOne,
+        Comp::D,
+        Comp::A,
+        Comp::NotD,
+        Comp::NotA,
+        Comp::NegD,
+        Comp::NegA,
+        Comp::DPlusOne,
+        Comp::APlusOne,
+        Comp::DMinusOne,
+        Comp::AMinusOne,
+        Comp::DPlusA,
+        Comp::DMinusA,
+        Comp::AMinusD,
+        Comp::DAndA,
+        Comp::DOrA,
+        Comp::M,
+        Comp::NotM,
+        Comp::NegM,
+        Comp::MPlusOne,
+        Comp::MMinusOne,
+        Comp::DPlusM,
+        Comp::DMinusM,
+        Comp::MMinusD,
+        Comp::DAndM,
+        Comp::DOrM,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         self.into()
     }
+
+    pub fn reads_a(&self) -> bool {
+        self.as_str().contains('A')
+    }
+
+    pub fn reads_d(&self) -> bool {
+        self.as_str().contains('D')
+    }
+
+    pub fn reads_m(&self) -> bool {
+        self.as_str().contains('M')
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Address {
     Value(u16),
     Variable(Cow<'static, str>),
+    /// Symbol arithmetic, resolved after all labels are known.
+    Expression(Expression),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CodeLine {
     Label(String),
     A(Address),
@@ -279,40 +431,73 @@ impl CodeLine {
         CodeLine::A(Address::Variable(symbol.into()))
     }
 
-    pub fn from_str(line: &str) -> Self {
-        if line.as_bytes()[0] == b'(' {
-            CodeLine::Label(line[1..line.len() - 1].to_string())
-        } else if line.as_bytes()[0] == b'@' {
-            let value = line[1..].parse().map(Address::Value)
-                .unwrap_or_else(|_| Address::Variable(line[1..].to_string().into()));
-            CodeLine::A(value)
+    /// Parses one line of Hack assembly.
+    ///
+    /// Comments and surrounding whitespace are skipped, so blank and comment-only lines yield `None`.
+    /// Error spans point into `line` as it was given.
+    pub fn parse(line_number: usize, line: &str) -> Result<Option<Self>, Error> {
+        let (offset, code) = strip_comment(line);
+        if code.is_empty() {
+            return Ok(None);
+        }
+        let error = |span: Range<usize>, kind| {
+            Error::new(line_number, offset + span.start..offset + span.end, kind)
+        };
+
+        if let Some(label) = code.strip_prefix('(') {
+            let Some(label) = label.strip_suffix(')') else {
+                return Err(error(0..code.len(), ErrorKind::UnterminatedLabel));
+            };
+            if !is_symbol(label) {
+                return Err(error(1..code.len() - 1, ErrorKind::InvalidSymbol(label.to_string())));
+            }
+            Ok(Some(CodeLine::Label(label.to_string())))
+        } else if let Some(value) = code.strip_prefix('@') {
+            let span = 1..code.len();
+            if is_symbol(value) {
+                Ok(Some(CodeLine::variable(value.to_string())))
+            } else if let Some(number) = parse_literal(value) {
+                match u16::try_from(number) {
+                    Ok(number) if number <= MAX_A_VALUE => Ok(Some(CodeLine::constant(number))),
+                    _ => Err(error(span, ErrorKind::AValueOutOfRange(value.to_string()))),
+                }
+            } else if let Some(expression) = Expression::parse(value) {
+                Ok(Some(CodeLine::A(Address::Expression(expression))))
+            } else if is_literal(value) {
+                Err(error(span, ErrorKind::InvalidNumber(value.to_string())))
+            } else {
+                Err(error(span, ErrorKind::InvalidSymbol(value.to_string())))
+            }
         } else {
-            let mut dest = Dest { a: false, m: false, d: false };
-            let mut jump = Jump::Null;
-            let mut comp = &line[..];
-            if let Some(idx) = line.find('=') {
-                let (d, c) = line.split_at(idx);
-                for c in d.chars() {
+            let mut dest = Dest::default();
+            let mut comp_start = 0;
+            if let Some(idx) = code.find('=') {
+                for (i, c) in code[..idx].char_indices() {
                     match c {
                         'A' => dest.a = true,
                         'M' => dest.m = true,
                         'D' => dest.d = true,
-                        _ => panic!("Invalid dest {}", d)
+                        _ => return Err(error(i..i + c.len_utf8(), ErrorKind::IllegalDest(c))),
                     }
                 }
-                comp = &c[1..];
+                comp_start = idx + 1;
             }
-            if let Some(idx) = comp.find(';') {
-                let (c, j) = comp.split_at(idx);
-                comp = c;
-                jump = j[1..].parse().unwrap_or_else(|e| panic!("Invalid jump {}: {:?}", j, e));
+            let mut comp_end = code.len();
+            let mut jump = Jump::Null;
+            if let Some(idx) = code[comp_start..].find(';') {
+                comp_end = comp_start + idx;
+                let j = &code[comp_end + 1..];
+                jump = j.parse()
+                    .map_err(|_| error(comp_end + 1..code.len(), ErrorKind::UnknownJump(j.to_string())))?;
             }
-            let comp = comp.parse().unwrap_or_else(|e| panic!("Invalid comp {}: {:?}", comp, e));
-            CodeLine::C {
+            let c = &code[comp_start..comp_end];
+            let comp = c.parse()
+                .map_err(|_| error(comp_start..comp_end, ErrorKind::UnknownComp(c.to_string())))?;
+            Ok(Some(CodeLine::C {
                 comp,
                 dest,
                 jump,
-            }
+            }))
         }
     }
 }
@@ -323,6 +508,7 @@ impl std::fmt::Display for CodeLine {
             CodeLine::Label(label) => write!(f, "({})", label),
             CodeLine::A(Address::Value(value)) => write!(f, "@{}", value),
             CodeLine::A(Address::Variable(symbol)) => write!(f, "@{}", symbol),
+            CodeLine::A(Address::Expression(expression)) => write!(f, "@{}", expression),
             CodeLine::C { comp, dest, jump } => {
                 write!(f, "{}{}{}",
                     dest,
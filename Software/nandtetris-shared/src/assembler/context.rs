@@ -0,0 +1,706 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::rc::Rc;
+use crate::{cfg, optimizer};
+use crate::object::{Object, Relocation, RelocationKind};
+use super::lint::lint;
+use super::preprocess::Preprocessor;
+use super::pseudo::{self, RETURN_LABEL_PREFIX};
+use super::statement::{Cells, Statement};
+use super::{
+    strip_comment, Address, AllocationError, CodeLine, Command, Error, ErrorKind, Errors, Expression, Instruction, Listing, ListingRow,
+    Location, LocationKind, RamOverflow, Severity, Source, SymbolKind, SymbolTable, Term, Warning, WarningKind,
+    MAX_A_VALUE, ROM_SIZE,
+};
+use super::predefined_symbols::SCREEN;
+
+/// A parsed line together with where it came from, for diagnostics.
+#[derive(Debug)]
+struct SourceLine {
+    /// Index of the top-level source this line belongs to.
+    source: usize,
+    file: Option<Rc<str>>,
+    line: usize,
+    span: Range<usize>,
+    expanded_from: Vec<Location>,
+    statement: Statement,
+    /// Part of the expansion of a pseudo-instruction.
+    pseudo: bool,
+}
+
+impl SourceLine {
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error::new(self.line, self.span.clone(), kind)
+            .with_file(self.file.clone())
+            .with_expansion(&self.expanded_from)
+    }
+
+    fn warning(&self, kind: WarningKind) -> Warning {
+        Warning {
+            file: self.file.clone(),
+            line: self.line,
+            span: self.span.clone(),
+            kind,
+            expanded_from: self.expanded_from.clone(),
+        }
+    }
+
+    fn location(&self) -> Location {
+        Location { file: self.file.clone(), line: self.line, kind: LocationKind::MacroCall }
+    }
+
+    /// The top-level source line, macro expansions and includes belong to where they were used.
+    fn root_line(&self) -> usize {
+        self.expanded_from.last().map(|x| x.line).unwrap_or(self.line)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Context {
+    pub symbol_table: SymbolTable,
+    /// Directories searched by `.include` after the directory of the including file.
+    pub include_paths: Vec<PathBuf>,
+    /// Initial RAM contents from `.word`, `.string` and `.data`, starting at address 0.
+    /// Empty if the last assembled program has no data.
+    pub ram_image: Vec<u16>,
+    /// Runs the [peephole optimizer](crate::optimizer) before resolving symbols.
+    /// Code reached through computed addresses is left as it is, see [`cfg::fixed_layout`].
+    pub optimize: bool,
+    /// Warns about code that cannot be reached and labels that are never used.
+    pub dead_code: bool,
+    /// Removes unreachable code and unused labels, implies `dead_code`.
+    /// Code reached through computed addresses is kept, see [`cfg::fixed_layout`].
+    pub strip_dead_code: bool,
+    /// Warns about likely mistakes such as jumps that also access M, implies `dead_code`.
+    pub lint: bool,
+    /// Whether variables and data allocated at or past `SCREEN` are errors or warnings.
+    pub ram_overflow: Severity,
+    /// Warnings of the last assembled program.
+    pub warnings: Vec<Warning>,
+    preprocessor: Preprocessor,
+}
+
+impl Context {
+    pub fn assemble(&mut self, content: &str) -> Result<Vec<Instruction>, Errors> {
+        self.assemble_sources(&[Source::new(content)])
+    }
+
+    /// Assembles several files as one program. They share the symbol table and macros,
+    /// and are placed in ROM in the given order.
+    pub fn assemble_sources(&mut self, sources: &[Source]) -> Result<Vec<Instruction>, Errors> {
+        let code_lines = self.parse_sources(sources, false)?;
+        let commands = self.resolve(&code_lines, None)?;
+        Ok(commands.iter().map(Instruction::from).collect())
+    }
+
+    /// Assembles several files into one relocatable module for [`link`](crate::object::link).
+    ///
+    /// Labels are exported with their address inside the module. Symbols that are neither defined
+    /// nor predefined are imported instead of becoming variables. Expressions may add constants
+    /// to at most one label or imported symbol.
+    pub fn assemble_object(&mut self, sources: &[Source]) -> Result<Object, Errors> {
+        let code_lines = self.parse_sources(sources, true)?;
+        let mut object = Object::default();
+        let commands = self.resolve(&code_lines, Some(&mut object))?;
+        object.code = commands.iter().map(|x| u16::from(Instruction::from(x))).collect();
+        let labels = code_lines.iter()
+            .filter_map(|line| match &line.statement {
+                Statement::Code(CodeLine::Label(name)) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        object.exports = self.symbol_table.sorted()
+            .into_iter()
+            .filter(|(name, symbol)| symbol.kind == SymbolKind::Label && labels.contains(name))
+            .filter(|(name, _)| !name.starts_with(RETURN_LABEL_PREFIX))
+            .map(|(name, symbol)| (name.to_string(), symbol.address))
+            .collect();
+        Ok(object)
+    }
+
+    /// Assembles already parsed code, e.g. the output of the VM translator.
+    /// Errors use the 1-based position in `code_lines` as their line number.
+    pub fn assemble_lines(&mut self, code_lines: impl IntoIterator<Item = CodeLine>) -> Result<Vec<Instruction>, Errors> {
+        let code_lines = code_lines.into_iter()
+            .enumerate()
+            .map(|(idx, code)| SourceLine {
+                source: 0,
+                file: None,
+                line: idx + 1,
+                span: 0..code.to_string().len(),
+                expanded_from: Vec::new(),
+                statement: Statement::Code(code),
+                pseudo: false,
+            })
+            .collect::<Vec<_>>();
+        self.warnings.clear();
+        let code_lines = self.eliminate_dead_code(code_lines, false);
+        let code_lines = self.optimized(code_lines);
+        let commands = self.resolve(&code_lines, None)?;
+        Ok(commands.iter().map(Instruction::from).collect())
+    }
+
+    /// Assembles `content` and keeps one row per source line, see [`Listing`].
+    pub fn assemble_listing(&mut self, content: &str) -> Result<Listing, Errors> {
+        self.assemble_sources_listing(&[Source::new(content)])
+    }
+
+    pub fn assemble_sources_listing(&mut self, sources: &[Source]) -> Result<Listing, Errors> {
+        let code_lines = self.parse_sources(sources, false)?;
+        let commands = self.resolve(&code_lines, None)?;
+
+        let mut code_lines = code_lines.iter().peekable();
+        let mut commands = commands.iter();
+        let mut address = 0;
+        let mut rows = Vec::new();
+        for (source_idx, source) in sources.iter().enumerate() {
+            for (idx, text) in source.content.lines().enumerate() {
+                let mut row = ListingRow {
+                    file: source.name.clone(),
+                    line: idx + 1,
+                    address: None,
+                    instructions: Vec::new(),
+                    expansion: Vec::new(),
+                    source: text.to_string(),
+                };
+                let mut pseudo = false;
+                while let Some(code_line) = code_lines.next_if(|x| x.source == source_idx && x.root_line() == idx + 1) {
+                    pseudo |= code_line.pseudo;
+                    if let Statement::Code(code) = &code_line.statement {
+                        row.expansion.push(code.to_string());
+                    }
+                    match &code_line.statement {
+                        Statement::Code(CodeLine::Label(_)) => {
+                            row.address.get_or_insert(address);
+                        }
+                        Statement::Code(_) => {
+                            row.address.get_or_insert(address);
+                            let command = commands.next().expect("Every instruction resolves to a command");
+                            row.instructions.push(Instruction::from(command));
+                            address += 1;
+                        }
+                        Statement::Equ { .. } | Statement::Data { .. } => {}
+                    }
+                }
+                if !pseudo {
+                    row.expansion.clear();
+                }
+                rows.push(row);
+            }
+        }
+        Ok(Listing { rows })
+    }
+
+    /// Assembles the sources into equivalent assembly without symbols, like the `L` files of
+    /// the nand2tetris projects: every A-instruction is numeric and labels are gone.
+    /// Comments name the resolved symbols, labels and constants. Returns the text together with
+    /// the instructions, which it assembles to.
+    pub fn assemble_symbol_less(&mut self, sources: &[Source]) -> Result<(String, Vec<Instruction>), Errors> {
+        let code_lines = self.parse_sources(sources, false)?;
+        let commands = self.resolve(&code_lines, None)?;
+
+        let instructions = commands.iter().map(Instruction::from).collect();
+        let mut commands = commands.iter();
+        let mut result = String::new();
+        for line in &code_lines {
+            let text = match &line.statement {
+                Statement::Code(CodeLine::Label(name)) => format!("// ({})", name),
+                Statement::Code(code) => match (code, commands.next().expect("Every instruction resolves to a command")) {
+                    (CodeLine::A(Address::Variable(name)), Command::A(value)) => format!("@{} // {}", value, name),
+                    (CodeLine::A(Address::Expression(expression)), Command::A(value)) => {
+                        format!("@{} // {}", value, expression)
+                    }
+                    _ => code.to_string(),
+                },
+                Statement::Equ { name, .. } | Statement::Data { name, .. } => {
+                    let value = self.symbol_table.get(name).expect("Resolved symbols are defined");
+                    format!("// {} = {}", name, value)
+                }
+            };
+            result.push_str(&text);
+            result.push('\n');
+        }
+        Ok((result, instructions))
+    }
+
+    pub fn parse_file(&mut self, content: &str) -> Result<Vec<Command>, Errors> {
+        let code_lines = self.parse_sources(&[Source::new(content)], false)?;
+        self.resolve(&code_lines, None)
+    }
+
+    /// Parses and preprocesses the sources, `object` treats every label as used by other modules.
+    fn parse_sources(&mut self, sources: &[Source], object: bool) -> Result<Vec<SourceLine>, Errors> {
+        let mut code_lines = Vec::new();
+        let mut all_errors = Vec::new();
+        let mut constants = self.symbol_table.clone();
+        let mut calls = 0;
+        for (source_idx, source) in sources.iter().enumerate() {
+            let mut errors = Vec::new();
+            for text in self.preprocessor.run(source, &self.include_paths, &mut constants, &mut errors) {
+                let (offset, code_text) = strip_comment(&text.text);
+                let source_line = |statement, pseudo| SourceLine {
+                    source: source_idx,
+                    file: text.file.clone(),
+                    line: text.line,
+                    span: offset..offset + code_text.len(),
+                    expanded_from: text.expanded_from.clone(),
+                    statement,
+                    pseudo,
+                };
+                let statements: Result<Vec<_>, _> = match pseudo::expand(text.line, &text.text, &mut calls) {
+                    Ok(Some(lines)) => Ok(lines.into_iter().map(|x| (Statement::Code(x), true)).collect()),
+                    Ok(None) => Statement::parse(text.line, &text.text).map(|x| x.map(|x| (x, false)).into_iter().collect()),
+                    Err(e) => Err(e),
+                };
+                match statements {
+                    Ok(statements) => {
+                        code_lines.extend(statements.into_iter().map(|(statement, pseudo)| source_line(statement, pseudo)));
+                    }
+                    Err(e) => errors.push(e.with_file(text.file).with_expansion(&text.expanded_from)),
+                }
+            }
+            errors.sort_by_key(|e| e.root().1);
+            all_errors.extend(errors);
+        }
+        if !all_errors.is_empty() {
+            return Err(Errors(all_errors));
+        }
+        self.warnings.clear();
+        let code_lines = self.eliminate_dead_code(code_lines, object);
+        Ok(self.optimized(code_lines))
+    }
+
+    /// Reports and strips dead code if enabled, see [`ControlFlowGraph`](cfg::ControlFlowGraph).
+    fn eliminate_dead_code(&mut self, code_lines: Vec<SourceLine>, object: bool) -> Vec<SourceLine> {
+        if !self.dead_code && !self.strip_dead_code && !self.lint {
+            return code_lines;
+        }
+        let (indices, code): (Vec<_>, Vec<_>) = code_lines.iter()
+            .enumerate()
+            .filter_map(|(idx, line)| match &line.statement {
+                Statement::Code(code) => Some((idx, code.clone())),
+                _ => None,
+            })
+            .unzip();
+        // labels stored by data directives or used in constants, all labels of an object
+        let directive_symbols = directive_symbols(&code_lines);
+        let mut external = directive_symbols.clone();
+        if object {
+            external.extend(code_lines.iter().filter_map(|line| match &line.statement {
+                Statement::Code(CodeLine::Label(name)) => Some(name.as_str()),
+                _ => None,
+            }));
+        }
+
+        let graph = cfg::ControlFlowGraph::new(&code);
+        let mut dead = vec![false; code.len()];
+        for (block, reachable) in graph.blocks.iter().zip(graph.reachable(&external)) {
+            if !reachable {
+                for idx in block.lines.clone() {
+                    dead[idx] = !matches!(code[idx], CodeLine::Label(_));
+                }
+            }
+        }
+
+        // one warning per run of dead instructions, labels do not end a run
+        let mut warnings = Vec::new();
+        let mut run: Option<(usize, usize)> = None;
+        for (idx, line) in code.iter().enumerate() {
+            if matches!(line, CodeLine::Label(_)) {
+                continue;
+            }
+            match (&mut run, dead[idx]) {
+                (Some((_, count)), true) => *count += 1,
+                (None, true) => run = Some((idx, 1)),
+                (Some((start, count)), false) => {
+                    warnings.push((*start, WarningKind::UnreachableCode(*count)));
+                    run = None;
+                }
+                (None, false) => {}
+            }
+        }
+        warnings.extend(run.map(|(start, count)| (start, WarningKind::UnreachableCode(count))));
+        for idx in cfg::unused_labels(&code, &external) {
+            let CodeLine::Label(name) = &code[idx] else {
+                continue;
+            };
+            warnings.push((idx, WarningKind::UnusedLabel(name.clone())));
+        }
+        warnings.sort_by_key(|(idx, _)| *idx);
+        self.warnings.extend(warnings.into_iter().map(|(idx, kind)| code_lines[indices[idx]].warning(kind)));
+
+        if !self.strip_dead_code {
+            return code_lines;
+        }
+        // code reached through computed addresses must stay where it is, even if it looks dead
+        let fixed = cfg::fixed_layout(&code, &directive_symbols);
+        let dead = dead.into_iter().zip(fixed).map(|(dead, fixed)| dead && !fixed).collect::<Vec<_>>();
+        // labels only used by removed code become unused as well
+        let live = code.into_iter().zip(&dead).filter(|(_, dead)| !**dead).map(|(x, _)| x).collect::<Vec<_>>();
+        let unused = cfg::unused_labels(&live, &external)
+            .into_iter()
+            .filter_map(|idx| match &live[idx] {
+                CodeLine::Label(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let mut dead = dead.into_iter();
+        code_lines.into_iter()
+            .filter(|line| match &line.statement {
+                Statement::Code(CodeLine::Label(name)) => {
+                    dead.next();
+                    !unused.contains(name)
+                }
+                Statement::Code(_) => !dead.next().expect("One flag per line"),
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// Optimizes the instructions if enabled, directives stay where they are.
+    fn optimized(&self, code_lines: Vec<SourceLine>) -> Vec<SourceLine> {
+        if !self.optimize {
+            return code_lines;
+        }
+        let (indices, code): (Vec<_>, Vec<_>) = code_lines.iter()
+            .enumerate()
+            .filter_map(|(idx, line)| match &line.statement {
+                Statement::Code(code) => Some((idx, code.clone())),
+                _ => None,
+            })
+            .unzip();
+        // fixed lines stay as they are, the runs between them start and end at labels
+        let fixed = cfg::fixed_layout(&code, &directive_symbols(&code_lines));
+        let mut optimized = Vec::with_capacity(code.len());
+        let mut run = Vec::new();
+        for (line, fixed) in indices.into_iter().zip(code).zip(fixed) {
+            if fixed {
+                optimized.extend(optimizer::optimize_with(std::mem::take(&mut run)));
+                optimized.push(line);
+            } else {
+                run.push(line);
+            }
+        }
+        optimized.extend(optimizer::optimize_with(run));
+        let mut code = optimized.into_iter().peekable();
+        code_lines.into_iter()
+            .enumerate()
+            .filter_map(|(idx, mut line)| {
+                if let Statement::Code(_) = line.statement {
+                    let (_, code) = code.next_if(|(kept, _)| *kept == idx)?;
+                    line.statement = Statement::Code(code);
+                }
+                Some(line)
+            })
+            .collect()
+    }
+
+    /// Resolves symbols and encodes the instructions. With `object`, labels stay relative to the
+    /// start of the code and undefined symbols are imported, both are recorded as relocations.
+    fn resolve(&mut self, code_lines: &[SourceLine], mut object: Option<&mut Object>) -> Result<Vec<Command>, Errors> {
+        let mut errors = Vec::new();
+        let mut defined_at = HashMap::new();
+        // labels and constants whose value moves with the module
+        let mut relative = HashSet::new();
+        let mut line_number = 0;
+        let mut rom_overflow = None;
+        for line in code_lines.iter() {
+            match &line.statement {
+                Statement::Code(CodeLine::Label(label)) => {
+                    let address = u16::try_from(line_number).unwrap_or(u16::MAX);
+                    match self.symbol_table.insert(label.clone().into(), address, SymbolKind::Label) {
+                        Ok(()) => {
+                            defined_at.insert(label.as_str(), line.location());
+                            relative.insert(label.clone());
+                        }
+                        Err(address) => {
+                            let defined_at = defined_at.get(label.as_str()).cloned().map(Box::new);
+                            errors.push(line.error(ErrorKind::DuplicateLabel { name: label.clone(), address, defined_at }));
+                        }
+                    }
+                }
+                Statement::Code(_) => {
+                    if line_number == ROM_SIZE {
+                        rom_overflow = Some(line);
+                    }
+                    line_number += 1;
+                }
+                Statement::Equ { .. } | Statement::Data { .. } => {}
+            }
+        }
+        if let Some(line) = rom_overflow {
+            errors.push(line.error(ErrorKind::RomOverflow(line_number)));
+        }
+        if !errors.is_empty() {
+            return Err(sorted_errors(code_lines, errors));
+        }
+
+        // Constants and data may refer to labels and to symbols defined above them.
+        self.ram_image.clear();
+        for line in code_lines.iter() {
+            match &line.statement {
+                Statement::Equ { name, value } if object.is_some() => {
+                    let value = match relocatable(value, &self.symbol_table, &relative) {
+                        Ok((value, None)) => value,
+                        Ok((value, Some(RelocationKind::Local))) => {
+                            relative.insert(name.clone());
+                            value
+                        }
+                        Ok((_, Some(RelocationKind::Import(undefined)))) => {
+                            errors.push(line.error(ErrorKind::UndefinedSymbol(undefined)));
+                            continue;
+                        }
+                        Err(kind) => {
+                            errors.push(line.error(kind));
+                            continue;
+                        }
+                    };
+                    self.define_constant(line, name, value, &mut errors);
+                }
+                Statement::Equ { name, value } => {
+                    let value = match value.evaluate(&self.symbol_table) {
+                        Ok(value) => value,
+                        Err(kind) => {
+                            errors.push(line.error(kind));
+                            continue;
+                        }
+                    };
+                    self.define_constant(line, name, value, &mut errors);
+                }
+                Statement::Data { .. } if object.is_some() => errors.push(line.error(ErrorKind::DataInObject)),
+                Statement::Data { name, cells } => match self.define_data(name, cells) {
+                    Ok((address, size)) => self.check_ram(line, name, address, size, &mut errors),
+                    Err(kind) => errors.push(line.error(kind)),
+                },
+                Statement::Code(_) => {}
+            }
+        }
+
+        let mut commands = Vec::new();
+        for line in code_lines.iter() {
+            let Statement::Code(code) = &line.statement else {
+                continue;
+            };
+            let command = match code {
+                CodeLine::A(address) if object.is_some() => {
+                    let expression = match address {
+                        Address::Value(value) => Expression { terms: vec![(false, Term::Number(i64::from(*value)))] },
+                        Address::Variable(name) => Expression { terms: vec![(false, Term::Symbol(name.to_string()))] },
+                        Address::Expression(expression) => expression.clone(),
+                    };
+                    let (value, kind) = match relocatable(&expression, &self.symbol_table, &relative) {
+                        Ok(result) => result,
+                        Err(kind) => {
+                            errors.push(line.error(kind));
+                            continue;
+                        }
+                    };
+                    let value = match u16::try_from(value) {
+                        Ok(value) if value <= MAX_A_VALUE => value,
+                        _ => {
+                            errors.push(line.error(ErrorKind::AValueOutOfRange(expression.to_string())));
+                            continue;
+                        }
+                    };
+                    if let (Some(object), Some(kind)) = (object.as_deref_mut(), kind) {
+                        if let RelocationKind::Import(name) = &kind {
+                            if !object.imports.contains(name) {
+                                object.imports.push(name.clone());
+                            }
+                        }
+                        object.relocations.push(Relocation { offset: commands.len() as u16, kind });
+                    }
+                    Command::A(value)
+                }
+                CodeLine::A(Address::Variable(symbol)) => {
+                    let next_address = self.symbol_table.next_address();
+                    let Ok(address) = self.symbol_table.get_or_insert(symbol.clone()) else {
+                        let overflow = RamOverflow { name: symbol.to_string(), address: next_address, size: 1 };
+                        errors.push(line.error(ErrorKind::RamOverflow(overflow)));
+                        continue;
+                    };
+                    if self.symbol_table.next_address() != next_address {
+                        self.check_ram(line, symbol, address, 1, &mut errors);
+                    }
+                    if address > MAX_A_VALUE {
+                        errors.push(line.error(ErrorKind::AValueOutOfRange(symbol.to_string())));
+                    }
+                    Command::A(address)
+                }
+                CodeLine::A(Address::Value(address)) => {
+                    Command::A(*address)
+                }
+                CodeLine::A(Address::Expression(expression)) => {
+                    match expression.evaluate(&self.symbol_table).map(u16::try_from) {
+                        Ok(Ok(value)) if value <= MAX_A_VALUE => Command::A(value),
+                        Ok(_) => {
+                            errors.push(line.error(ErrorKind::AValueOutOfRange(expression.to_string())));
+                            continue;
+                        }
+                        Err(kind) => {
+                            errors.push(line.error(kind));
+                            continue;
+                        }
+                    }
+                }
+                CodeLine::C { comp, dest, jump } => {
+                    Command::C { comp: *comp, dest: *dest, jump: *jump }
+                }
+                CodeLine::Label(_) => continue,
+            };
+            commands.push(command);
+        }
+        if !errors.is_empty() {
+            return Err(sorted_errors(code_lines, errors));
+        }
+
+        if self.lint {
+            let (lines, code): (Vec<_>, Vec<_>) = code_lines.iter()
+                .filter_map(|line| match &line.statement {
+                    Statement::Code(code) => Some((line, code.clone())),
+                    _ => None,
+                })
+                .unzip();
+            let warnings = lint(&code, &self.symbol_table);
+            self.warnings.extend(warnings.into_iter().map(|(idx, kind)| lines[idx].warning(kind)));
+        }
+        // dead code, RAM and lint warnings come from separate passes
+        let sources = source_indices(code_lines);
+        self.warnings.sort_by_key(|w| {
+            let (file, line) = w.root();
+            (sources.get(&file).copied(), line)
+        });
+        Ok(commands)
+    }
+
+    fn define_constant(&mut self, line: &SourceLine, name: &str, value: i64, errors: &mut Vec<Error>) {
+        let Ok(value) = u16::try_from(value) else {
+            errors.push(line.error(ErrorKind::AValueOutOfRange(value.to_string())));
+            return;
+        };
+        if self.symbol_table.insert(name.to_string().into(), value, SymbolKind::Constant).is_err() {
+            errors.push(line.error(ErrorKind::AlreadyDefined(name.to_string())));
+        }
+    }
+
+    /// Reports a new variable or data block that does not fit below `SCREEN`.
+    fn check_ram(&mut self, line: &SourceLine, name: &str, address: u16, size: u16, errors: &mut Vec<Error>) {
+        if u32::from(address) + u32::from(size) <= u32::from(SCREEN.value) {
+            return;
+        }
+        let overflow = RamOverflow { name: name.to_string(), address: u32::from(address), size };
+        match self.ram_overflow {
+            Severity::Warning => self.warnings.push(line.warning(WarningKind::RamOverflow(overflow))),
+            Severity::Error => errors.push(line.error(ErrorKind::RamOverflow(overflow))),
+        }
+    }
+
+    /// Reserves the cells of a data directive and writes their values into the RAM image.
+    /// Returns the address and size of the block.
+    fn define_data(&mut self, name: &str, cells: &Cells) -> Result<(u16, u16), ErrorKind> {
+        let evaluate = |expression: &Expression| expression.evaluate(&self.symbol_table);
+        let values = match cells {
+            Cells::Words(values) => values.iter()
+                .map(|x| match evaluate(x)? {
+                    // negative values are stored in two's complement
+                    value @ -0x8000..=0xFFFF => Ok(value as u16),
+                    _ => Err(ErrorKind::WordOutOfRange(x.to_string())),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Cells::Zeroed(count) => match evaluate(count)? {
+                count @ 0..=0x7FFF => vec![0; count as usize],
+                _ => return Err(ErrorKind::AValueOutOfRange(count.to_string())),
+            },
+        };
+        let next_address = self.symbol_table.next_address();
+        let size = values.len() as u16;
+        let address = self.symbol_table.allocate(name.to_string().into(), size).map_err(|e| match e {
+            AllocationError::AlreadyDefined(_) => ErrorKind::AlreadyDefined(name.to_string()),
+            // reported whatever `ram_overflow` says, the cells do not exist
+            AllocationError::RamExhausted => {
+                ErrorKind::RamOverflow(RamOverflow { name: name.to_string(), address: next_address, size })
+            }
+        })?;
+        let start = usize::from(address);
+        if self.ram_image.len() < start + values.len() {
+            self.ram_image.resize(start + values.len(), 0);
+        }
+        self.ram_image[start..start + values.len()].copy_from_slice(&values);
+        Ok((address, size))
+    }
+}
+
+/// Index of the top-level source each file name belongs to, to order diagnostics like the sources.
+fn source_indices(code_lines: &[SourceLine]) -> HashMap<Option<&str>, usize> {
+    let mut sources = HashMap::new();
+    for line in code_lines {
+        let file = line.expanded_from.last().map_or(&line.file, |x| &x.file);
+        sources.entry(file.as_deref()).or_insert(line.source);
+    }
+    sources
+}
+
+/// Symbols used by `.equ` values and data directives, labels among them may be offset or stored.
+fn directive_symbols(code_lines: &[SourceLine]) -> Vec<&str> {
+    code_lines.iter()
+        .flat_map(|line| match &line.statement {
+            Statement::Equ { value, .. } => vec![value],
+            Statement::Data { cells: Cells::Words(values), .. } => values.iter().collect(),
+            Statement::Data { cells: Cells::Zeroed(count), .. } => vec![count],
+            Statement::Code(_) => Vec::new(),
+        })
+        .flat_map(|x| &x.terms)
+        .filter_map(|(_, term)| match term {
+            Term::Symbol(name) => Some(name.as_str()),
+            Term::Number(_) => None,
+        })
+        .collect()
+}
+
+/// Orders errors by source and line, the passes of [`Context::resolve`] find them out of order.
+/// Errors in macro expansions and includes belong to where they were used.
+fn sorted_errors(code_lines: &[SourceLine], mut errors: Vec<Error>) -> Errors {
+    let sources = source_indices(code_lines);
+    errors.sort_by_key(|e| (sources.get(&e.root().0).copied(), e.root().1));
+    Errors(errors)
+}
+
+/// Value of an expression in a relocatable object and how the linker has to fix it up.
+/// Undefined symbols count as 0, the value is then the addend of the imported symbol.
+fn relocatable(
+    expression: &Expression,
+    symbol_table: &SymbolTable,
+    relative: &HashSet<String>,
+) -> Result<(i64, Option<RelocationKind>), ErrorKind> {
+    let mut bases = 0;
+    let mut imports = Vec::new();
+    for (negative, term) in &expression.terms {
+        let Term::Symbol(name) = term else {
+            continue;
+        };
+        if relative.contains(name) {
+            bases += if *negative { -1 } else { 1 };
+        } else if symbol_table.get(name).is_none() {
+            imports.push((*negative, name));
+        }
+    }
+    let kind = match (bases, &imports[..]) {
+        (0, []) => None,
+        (1, []) => Some(RelocationKind::Local),
+        (0, [(false, name)]) => Some(RelocationKind::Import(name.to_string())),
+        _ => return Err(ErrorKind::NotRelocatable(expression.to_string())),
+    };
+    let value = expression.evaluate_with(|name| Some(symbol_table.get(name).map_or(0, i64::from)))?;
+    // the word holds the offset the linker adds the import's address to
+    if value < 0 && matches!(kind, Some(RelocationKind::Import(_))) {
+        return Err(ErrorKind::NegativeImportOffset(expression.to_string()));
+    }
+    Ok((value, kind))
+}
+
+/// Assembles `content` with a fresh symbol table into machine words.
+pub fn assemble(content: &str) -> Result<Vec<u16>, Errors> {
+    let instructions = Context::default().assemble(content)?;
+    Ok(instructions.into_iter().map(u16::from).collect())
+}
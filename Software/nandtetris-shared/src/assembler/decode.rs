@@ -0,0 +1,83 @@
+use super::{Address, CodeLine, Comp, Dest, Jump, MAX_A_VALUE};
+
+/// A machine word that has no assembly representation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The `a cccccc` bits of a C-instruction match no `Comp` variant.
+    UnknownComp { word: u16, comp: u16 },
+    /// A C-instruction whose unused bits 14 and 13 are not `11`.
+    InvalidPrefix(u16),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DecodeError::UnknownComp { word, comp } => {
+                write!(f, "{:016b}: unknown comp bits {:07b}", word, comp)
+            }
+            DecodeError::InvalidPrefix(word) => {
+                write!(f, "{:016b}: C-instruction does not start with 111", word)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<u16> for Dest {
+    /// Decodes the `ddd` bits, given in the lowest three bits of `value`.
+    fn from(value: u16) -> Dest {
+        Dest {
+            a: value & 0b100 != 0,
+            d: value & 0b010 != 0,
+            m: value & 0b001 != 0,
+        }
+    }
+}
+
+impl From<u16> for Jump {
+    /// Decodes the `jjj` bits, given in the lowest three bits of `value`.
+    fn from(value: u16) -> Jump {
+        match value & 0b111 {
+            0b000 => Jump::Null,
+            0b001 => Jump::JGT,
+            0b010 => Jump::JEQ,
+            0b011 => Jump::JGE,
+            0b100 => Jump::JLT,
+            0b101 => Jump::JNE,
+            0b110 => Jump::JLE,
+            _ => Jump::JMP,
+        }
+    }
+}
+
+impl TryFrom<u16> for Comp {
+    type Error = u16;
+
+    /// Decodes the `a cccccc` bits, given in the lowest seven bits of `value`.
+    /// Returns the bits back if no variant has this encoding.
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        let bits = value & 0b111_1111;
+        Comp::ALL.iter().copied().find(|comp| u16::from(comp) == bits).ok_or(bits)
+    }
+}
+
+impl TryFrom<u16> for CodeLine {
+    type Error = DecodeError;
+
+    fn try_from(word: u16) -> Result<Self, Self::Error> {
+        if word <= MAX_A_VALUE {
+            return Ok(CodeLine::A(Address::Value(word)));
+        }
+        if word & 0b0110_0000_0000_0000 != 0b0110_0000_0000_0000 {
+            return Err(DecodeError::InvalidPrefix(word));
+        }
+        let comp = Comp::try_from(word >> 6)
+            .map_err(|comp| DecodeError::UnknownComp { word, comp })?;
+        Ok(CodeLine::C {
+            comp,
+            dest: Dest::from(word >> 3),
+            jump: Jump::from(word),
+        })
+    }
+}
@@ -0,0 +1,288 @@
+use std::ops::Range;
+use std::rc::Rc;
+use super::preprocess::{MAX_EXPANDED_LINES, MAX_REPEAT};
+use super::predefined_symbols::{KBD, SCREEN};
+use super::ROM_SIZE;
+
+/// An error found in a single source line.
+///
+/// `line` is 1-based, `span` holds the 0-based byte columns of the offending part of the line.
+/// `file` is `None` for sources without a name. For lines produced by a macro or an include,
+/// `expanded_from` lists the call sites and `.include` directives, innermost first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub file: Option<Rc<str>>,
+    pub line: usize,
+    pub span: Range<usize>,
+    pub kind: ErrorKind,
+    pub expanded_from: Vec<Location>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocationKind {
+    MacroCall,
+    Include,
+    /// A line of a `.rept` block.
+    Repeat,
+}
+
+/// A position in the source, used to track macro call sites and includes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: Option<Rc<str>>,
+    pub line: usize,
+    pub kind: LocationKind,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}", file, self.line),
+            None => write!(f, "line {}", self.line),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnknownComp(String),
+    UnknownJump(String),
+    IllegalDest(char),
+    UnterminatedLabel,
+    InvalidSymbol(String),
+    AValueOutOfRange(String),
+    InvalidNumber(String),
+    InvalidExpression(String),
+    UndefinedSymbol(String),
+    /// An expression whose value does not fit in 64 bits.
+    ExpressionOverflow(String),
+    AlreadyDefined(String),
+    InvalidString(String),
+    WordOutOfRange(String),
+    DuplicateLabel { name: String, address: u16, defined_at: Option<Box<Location>> },
+    UnknownDirective(String),
+    UnterminatedMacro(String),
+    UnexpectedEndMacro,
+    NestedMacroDefinition,
+    InvalidMacroName(String),
+    DuplicateMacro(String),
+    MacroArgumentCount { name: String, expected: usize, found: usize },
+    MacroRecursion(String),
+    InvalidInclude,
+    IncludeNotFound(String),
+    IncludeCycle(String),
+    /// A closing directive without its opening one, e.g. `.endif` without `.if`.
+    UnexpectedDirective { directive: &'static str, opening: &'static str },
+    /// A block directive without its closing one, e.g. `.rept` without `.endr`.
+    UnterminatedBlock { directive: &'static str, closing: &'static str },
+    DuplicateElse,
+    InvalidRepeatCount(i64),
+    /// `.rept` blocks and macros together expand to more than `MAX_EXPANDED_LINES` lines.
+    ExpansionLimit,
+    /// The program has more instructions than the ROM holds.
+    RomOverflow(usize),
+    RamOverflow(RamOverflow),
+    /// An expression in a relocatable object that is not a constant plus at most one label or import.
+    NotRelocatable(String),
+    /// An external symbol minus a constant, objects only store offsets the linker adds.
+    NegativeImportOffset(String),
+    /// Data directives allocate RAM, which only the linker can do.
+    DataInObject,
+    /// A pseudo-instruction with the wrong operands, holds its syntax.
+    PseudoUsage(&'static str),
+    /// A pseudo-instruction operand that reads A or M after the expansion loaded A.
+    OverwrittenRegister(String),
+}
+
+/// A variable or data block allocated at or past `SCREEN`, where it is not ordinary RAM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RamOverflow {
+    pub name: String,
+    /// Can be 65536 when every cell is taken.
+    pub address: u32,
+    pub size: u16,
+}
+
+impl std::fmt::Display for RamOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let last = self.address + u32::from(self.size.max(1)) - 1;
+        if self.size > 1 {
+            write!(f, "`{}` at RAM[{}..={}]", self.name, self.address, last)?;
+        } else {
+            write!(f, "`{}` at RAM[{}]", self.name, self.address)?;
+        }
+        let kbd = u32::from(KBD.value);
+        if last > kbd {
+            write!(f, " reaches past the end of RAM")
+        } else if last == kbd {
+            write!(f, " overlaps the KBD register")
+        } else {
+            write!(f, " overlaps SCREEN memory starting at {}", SCREEN.value)
+        }
+    }
+}
+
+impl Error {
+    pub fn new(line: usize, span: Range<usize>, kind: ErrorKind) -> Self {
+        Error { file: None, line, span, kind, expanded_from: Vec::new() }
+    }
+
+    pub fn with_file(mut self, file: Option<Rc<str>>) -> Self {
+        self.file = file;
+        self
+    }
+
+    pub fn with_expansion(mut self, expanded_from: &[Location]) -> Self {
+        self.expanded_from = expanded_from.to_vec();
+        self
+    }
+
+    /// The top-level source position, macro expansions and includes belong to where they were used.
+    pub fn root(&self) -> (Option<&str>, usize) {
+        match self.expanded_from.last() {
+            Some(location) => (location.file.as_deref(), location.line),
+            None => (self.file.as_deref(), self.line),
+        }
+    }
+
+    /// 1-based column of the first offending character.
+    pub fn column(&self) -> usize {
+        self.span.start + 1
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ErrorKind::UnknownComp(comp) => write!(f, "unknown comp `{}`", comp),
+            ErrorKind::UnknownJump(jump) => write!(f, "unknown jump `{}`", jump),
+            ErrorKind::IllegalDest(c) => write!(f, "illegal dest letter `{}`", c),
+            ErrorKind::UnterminatedLabel => write!(f, "unterminated label, expected `)`"),
+            ErrorKind::InvalidSymbol(symbol) => write!(f, "invalid symbol `{}`", symbol),
+            ErrorKind::AValueOutOfRange(value) => {
+                write!(f, "A-value `{}` does not fit in 15 bits", value)?;
+                // 16-bit values with the top bit set can be loaded inverted
+                match super::parse_literal(value) {
+                    Some(number) if (-0x8000..=0xFFFF).contains(&number) => {
+                        write!(f, ", load it with `@{}` followed by `A=!A`", !(number as u16))?;
+                    }
+                    _ => {}
+                }
+                Ok(())
+            }
+            ErrorKind::InvalidNumber(value) => write!(f, "invalid number `{}`", value),
+            ErrorKind::InvalidExpression(value) => write!(f, "invalid expression `{}`", value),
+            ErrorKind::UndefinedSymbol(name) => write!(f, "undefined symbol `{}` in expression", name),
+            ErrorKind::ExpressionOverflow(expression) => write!(f, "expression `{}` overflows", expression),
+            ErrorKind::AlreadyDefined(name) => write!(f, "symbol `{}` is already defined", name),
+            ErrorKind::InvalidString(value) => write!(f, "invalid string {}, expected `\"text\"`", value),
+            ErrorKind::WordOutOfRange(value) => write!(f, "value `{}` does not fit in 16 bits", value),
+            ErrorKind::DuplicateLabel { name, address, defined_at: Some(location) } => {
+                write!(f, "label `{}` is already defined at {} with address {}", name, location, address)
+            }
+            ErrorKind::DuplicateLabel { name, address, defined_at: None } => {
+                write!(f, "label `{}` is already defined with address {}", name, address)
+            }
+            ErrorKind::UnknownDirective(directive) => write!(f, "unknown directive `{}`", directive),
+            ErrorKind::UnterminatedMacro(name) => write!(f, "macro `{}` is missing `.endm`", name),
+            ErrorKind::UnexpectedEndMacro => write!(f, "`.endm` without `.macro`"),
+            ErrorKind::NestedMacroDefinition => write!(f, "macros cannot be defined inside a macro"),
+            ErrorKind::InvalidMacroName(name) => write!(f, "invalid macro name `{}`", name),
+            ErrorKind::DuplicateMacro(name) => write!(f, "macro `{}` is already defined", name),
+            ErrorKind::MacroArgumentCount { name, expected, found } => {
+                write!(f, "macro `{}` expects {} argument(s), found {}", name, expected, found)
+            }
+            ErrorKind::MacroRecursion(name) => write!(f, "macro `{}` expands recursively", name),
+            ErrorKind::InvalidInclude => write!(f, "expected `.include \"file.asm\"`"),
+            ErrorKind::IncludeNotFound(file) => write!(f, "cannot find included file `{}`", file),
+            ErrorKind::IncludeCycle(file) => write!(f, "`{}` includes itself", file),
+            ErrorKind::UnexpectedDirective { directive, opening } => write!(f, "`{}` without `{}`", directive, opening),
+            ErrorKind::UnterminatedBlock { directive, closing } => write!(f, "`{}` is missing `{}`", directive, closing),
+            ErrorKind::DuplicateElse => write!(f, "`.if` already has an `.else`"),
+            ErrorKind::InvalidRepeatCount(count) => {
+                write!(f, "repeat count {} must be between 0 and {}", count, MAX_REPEAT)
+            }
+            ErrorKind::ExpansionLimit => write!(f, "expansions produce more than {} lines", MAX_EXPANDED_LINES),
+            ErrorKind::RomOverflow(size) => {
+                write!(f, "program needs {} instructions, the ROM only holds {}", size, ROM_SIZE)
+            }
+            ErrorKind::RamOverflow(overflow) => write!(f, "{}", overflow),
+            ErrorKind::NotRelocatable(value) => {
+                write!(f, "`{}` cannot be relocated, use a constant plus at most one label or external symbol", value)
+            }
+            ErrorKind::NegativeImportOffset(value) => {
+                write!(f, "`{}` cannot be relocated, negative offsets from external symbols are not supported", value)
+            }
+            ErrorKind::DataInObject => write!(f, "data directives are not supported in relocatable objects"),
+            ErrorKind::PseudoUsage(usage) => write!(f, "expected `{}`", usage),
+            ErrorKind::OverwrittenRegister(comp) => {
+                write!(f, "comp `{}` reads A or M, which the pseudo-instruction overwrites", comp)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(f, "{}:{}: {}", self.line, self.column(), self.kind)?;
+        write_expansion(f, &self.expanded_from)
+    }
+}
+
+/// Appends where a line was expanded or included from, innermost first.
+pub(crate) fn write_expansion(f: &mut std::fmt::Formatter, expanded_from: &[Location]) -> std::fmt::Result {
+    for location in expanded_from {
+        match location.kind {
+            LocationKind::MacroCall => write!(f, ", expanded from {}", location)?,
+            LocationKind::Include => write!(f, ", included from {}", location)?,
+            LocationKind::Repeat => write!(f, ", repeated from {}", location)?,
+        }
+    }
+    Ok(())
+}
+
+impl std::error::Error for Error {}
+
+/// Every error found while assembling a program, in source order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Errors(pub Vec<Error>);
+
+impl Errors {
+    pub fn iter(&self) -> std::slice::Iter<'_, Error> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a Errors {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl std::fmt::Display for Errors {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (idx, error) in self.0.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Errors {}
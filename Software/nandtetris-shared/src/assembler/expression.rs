@@ -0,0 +1,117 @@
+use std::fmt::{self, Display};
+use super::{is_symbol, parse_literal, ErrorKind, SymbolTable};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Number(i64),
+    Symbol(String),
+}
+
+/// A sum of symbols and numbers, e.g. `SCREEN+32` or `END-1`.
+/// Symbols must be defined when the expression is evaluated, they are never allocated as variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expression {
+    /// Terms with their sign, `true` for subtraction.
+    pub terms: Vec<(bool, Term)>,
+}
+
+impl Expression {
+    /// Parses `term (('+' | '-') term)*`, where terms are symbols or literals.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut terms = Vec::new();
+        let mut negative = false;
+        let mut start = 0;
+        let mut in_char = false;
+        for (idx, c) in s.char_indices() {
+            match c {
+                '\'' => in_char = !in_char,
+                '+' | '-' if !in_char && idx > start => {
+                    terms.push((negative, Self::parse_term(&s[start..idx])?));
+                    negative = c == '-';
+                    start = idx + 1;
+                }
+                _ => {}
+            }
+        }
+        terms.push((negative, Self::parse_term(&s[start..])?));
+        Some(Expression { terms })
+    }
+
+    fn parse_term(s: &str) -> Option<Term> {
+        if is_symbol(s) {
+            Some(Term::Symbol(s.to_string()))
+        } else {
+            parse_literal(s).map(Term::Number)
+        }
+    }
+
+    /// A plain number or symbol, which needs no expression.
+    pub fn as_term(&self) -> Option<&Term> {
+        match &self.terms[..] {
+            [(false, term)] => Some(term),
+            _ => None,
+        }
+    }
+
+    /// Fails with [`ErrorKind::UndefinedSymbol`] for the first undefined symbol,
+    /// or [`ErrorKind::ExpressionOverflow`] if the sum does not fit in 64 bits.
+    pub fn evaluate(&self, symbol_table: &SymbolTable) -> Result<i64, ErrorKind> {
+        self.evaluate_with(|name| symbol_table.get(name).map(i64::from))
+    }
+
+    /// Like [`Expression::evaluate`], looking up symbols with `lookup`.
+    pub fn evaluate_with(&self, lookup: impl Fn(&str) -> Option<i64>) -> Result<i64, ErrorKind> {
+        self.terms.iter().try_fold(0i64, |acc, (negative, term)| {
+            let value = match term {
+                Term::Number(value) => *value,
+                Term::Symbol(name) => lookup(name).ok_or_else(|| ErrorKind::UndefinedSymbol(name.clone()))?,
+            };
+            let sum = if *negative { acc.checked_sub(value) } else { acc.checked_add(value) };
+            sum.ok_or_else(|| ErrorKind::ExpressionOverflow(self.to_string()))
+        })
+    }
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Term::Number(value) => write!(f, "{}", value),
+            Term::Symbol(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, (negative, term)) in self.terms.iter().enumerate() {
+            match (idx, negative) {
+                (_, true) => write!(f, "-")?,
+                (0, false) => {}
+                (_, false) => write!(f, "+")?,
+            }
+            write!(f, "{}", term)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_evaluates() {
+        let mut table = SymbolTable::default();
+        let expression = Expression::parse("SCREEN+0x20-'A'+R1").unwrap();
+        assert_eq!(expression.to_string(), "SCREEN+32-65+R1");
+        assert_eq!(expression.evaluate(&table), Ok(16384 + 32 - 65 + 1));
+
+        let expression = Expression::parse("-1+ball").unwrap();
+        assert_eq!(expression.evaluate(&table), Err(ErrorKind::UndefinedSymbol("ball".to_string())));
+        table.get_or_insert("ball".into()).unwrap();
+        assert_eq!(expression.evaluate(&table), Ok(15));
+
+        assert!(Expression::parse("END-").is_none());
+        assert!(Expression::parse("1abc+2").is_none());
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt::{self, Display};
+use super::{Comp, Dest, Jump};
+
+/// An instruction with every symbol resolved, ready to be encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    A(u16),
+    C {
+        comp: Comp,
+        dest: Dest,
+        jump: Jump,
+    }
+}
+
+/// A 16-bit Hack machine word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction(pub u16);
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016b}", self.0)
+    }
+}
+
+impl From<&Command> for Instruction {
+    fn from(value: &Command) -> Self {
+        match value {
+            Command::A(val) => Instruction(*val),
+            Command::C { comp, dest, jump } => {
+                let mut instruction = 0b111;
+                instruction = instruction << 7 | u16::from(comp);
+                instruction = instruction << 3 | u16::from(dest);
+                instruction = instruction << 3 | u16::from(jump);
+                Instruction(instruction)
+            }
+        }
+    }
+}
+
+impl From<Instruction> for u16 {
+    fn from(value: Instruction) -> u16 {
+        value.0
+    }
+}
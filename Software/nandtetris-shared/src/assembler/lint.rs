@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use super::{Address, CodeLine, Comp, Jump, SymbolKind, SymbolTable, WarningKind};
+
+/// Checks resolved code for mistakes the assembler accepts, returns warnings by index into `code`.
+/// Unreachable code and unused labels are reported by the dead code analysis instead.
+pub(crate) fn lint(code: &[CodeLine], symbol_table: &SymbolTable) -> Vec<(usize, WarningKind)> {
+    let mut warnings = Vec::new();
+    let mut variable_uses: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (idx, line) in code.iter().enumerate() {
+        match line {
+            CodeLine::C { comp, dest, jump } if *jump != Jump::Null => {
+                if comp.reads_m() || dest.m {
+                    warnings.push((idx, WarningKind::MemoryJump(line.to_string())));
+                }
+                let discarded = [Comp::DPlusOne, Comp::DMinusOne, Comp::APlusOne, Comp::AMinusOne, Comp::MPlusOne, Comp::MMinusOne];
+                if !(dest.a || dest.m || dest.d) && discarded.contains(comp) {
+                    warnings.push((idx, WarningKind::DiscardedComputation(comp.as_str())));
+                }
+            }
+            CodeLine::A(Address::Variable(name)) => {
+                let kind = symbol_table.get_symbol(name).map(|x| x.kind);
+                if kind == Some(SymbolKind::Variable) {
+                    variable_uses.entry(name).or_default().push(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let symbols = symbol_table.iter()
+        .filter(|(_, symbol)| matches!(symbol.kind, SymbolKind::Predefined | SymbolKind::Label))
+        .collect::<Vec<_>>();
+    for (variable, uses) in variable_uses {
+        if let Some((symbol, _)) = symbols.iter().find(|(symbol, _)| symbol.eq_ignore_ascii_case(variable)) {
+            let kind = WarningKind::CaseMismatch { variable: variable.to_string(), symbol: symbol.to_string() };
+            warnings.push((uses[0], kind));
+        }
+        if uses.len() == 1 {
+            warnings.push((uses[0], WarningKind::SingleUse(variable.to_string())));
+        }
+    }
+    warnings.sort_by_key(|(idx, _)| *idx);
+    warnings
+}
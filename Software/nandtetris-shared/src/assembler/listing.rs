@@ -0,0 +1,120 @@
+use std::fmt::{self, Display};
+use std::rc::Rc;
+use super::Instruction;
+
+/// One row per source line, matching ROM addresses to the code that produced them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Listing {
+    pub rows: Vec<ListingRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingRow {
+    pub file: Option<Rc<str>>,
+    /// 1-based source line number.
+    pub line: usize,
+    /// ROM address of the first instruction, or the address a label resolves to.
+    /// `None` for blank and comment-only lines.
+    pub address: Option<u16>,
+    pub instructions: Vec<Instruction>,
+    /// Labels and instructions the line expanded to if it holds a pseudo-instruction, else empty.
+    pub expansion: Vec<String>,
+    /// The original line, comments included.
+    pub source: String,
+}
+
+impl Listing {
+    pub fn instructions(&self) -> impl Iterator<Item = Instruction> + '_ {
+        self.rows.iter().flat_map(|row| row.instructions.iter().copied())
+    }
+}
+
+impl Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>5}  {:<16}  {:<4}  {:>5}  source", "addr", "binary", "hex", "line")?;
+        let several_files = self.rows.iter().any(|row| row.file != self.rows[0].file);
+        for (idx, row) in self.rows.iter().enumerate() {
+            if several_files && (idx == 0 || row.file != self.rows[idx - 1].file) {
+                writeln!(f, "// {}", row.file.as_deref().unwrap_or_default())?;
+            }
+            let address = row.address.map(|x| format!("{:05}", x)).unwrap_or_default();
+            if !row.expansion.is_empty() {
+                writeln!(f, "{:>5}  {:16}  {:4}  {:>5}  {}", address, "", "", row.line, row.source)?;
+                let mut address = usize::from(row.address.unwrap_or_default());
+                let mut instructions = row.instructions.iter();
+                for text in &row.expansion {
+                    if text.starts_with('(') {
+                        writeln!(f, "{:05}  {:16}  {:4}  {:5}  {}", address, "", "", "", text)?;
+                    } else {
+                        let instruction = instructions.next().expect("One instruction per expanded line");
+                        writeln!(f, "{:05}  {}  {:04X}  {:5}  {}", address, instruction, instruction.0, "", text)?;
+                        address += 1;
+                    }
+                }
+                continue;
+            }
+            let line = match row.instructions.split_first() {
+                Some((first, _)) => format!("{:>5}  {}  {:04X}  {:>5}  {}", address, first, first.0, row.line, row.source),
+                None => format!("{:>5}  {:16}  {:4}  {:>5}  {}", address, "", "", row.line, row.source),
+            };
+            writeln!(f, "{}", line.trim_end())?;
+            let address = usize::from(row.address.unwrap_or_default());
+            for (idx, instruction) in row.instructions.iter().enumerate().skip(1) {
+                writeln!(f, "{:05}  {}  {:04X}", address + idx, instruction, instruction.0)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembler::Context;
+
+    #[test]
+    fn listing() {
+        let input = "// Computes M[2] = max(M[0], M[1])\n\n   @0\n   D=M   // first\n(END)\n   @END\n   0;JMP\n";
+        let listing = Context::default().assemble_listing(input).unwrap();
+        assert_eq!(listing.to_string(), [
+            " addr  binary            hex    line  source",
+            "                                   1  // Computes M[2] = max(M[0], M[1])",
+            "                                   2",
+            "00000  0000000000000000  0000      3     @0",
+            "00001  1111110000010000  FC10      4     D=M   // first",
+            "00002                              5  (END)",
+            "00002  0000000000000010  0002      6     @END",
+            "00003  1110101010000111  EA87      7     0;JMP",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn shows_pseudo_instruction_expansions() {
+        let input = "CALL DOUBLE\nJMP END\n(DOUBLE)\n  RET\n(END)\n";
+        let listing = Context::default().assemble_listing(input).unwrap();
+        assert_eq!(listing.to_string(), [
+            " addr  binary            hex    line  source",
+            "00000                              1  CALL DOUBLE",
+            "00000  0000000000001000  0008         @$ret.0",
+            "00001  1110110000010000  EC10         D=A",
+            "00002  0000000000000000  0000         @SP",
+            "00003  1111110111001000  FDC8         M=M+1",
+            "00004  1111110010100000  FCA0         A=M-1",
+            "00005  1110001100001000  E308         M=D",
+            "00006  0000000000001010  000A         @DOUBLE",
+            "00007  1110101010000111  EA87         0;JMP",
+            "00008                                 ($ret.0)",
+            "00008                              2  JMP END",
+            "00008  0000000000001110  000E         @END",
+            "00009  1110101010000111  EA87         0;JMP",
+            "00010                              3  (DOUBLE)",
+            "00010                              4    RET",
+            "00010  0000000000000000  0000         @SP",
+            "00011  1111110010101000  FCA8         AM=M-1",
+            "00012  1111110000100000  FC20         A=M",
+            "00013  1110101010000111  EA87         0;JMP",
+            "00014                              5  (END)",
+            "",
+        ].join("\n"));
+    }
+}
@@ -0,0 +1,207 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+use super::Instruction;
+
+/// File formats an assembled program can be written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// ASCII `{:016b}` lines, as read by the nand2tetris tools.
+    #[default]
+    Hack,
+    /// Raw ROM image, two bytes per word, high byte first.
+    BinaryBigEndian,
+    /// Raw ROM image, two bytes per word, low byte first.
+    BinaryLittleEndian,
+    /// Intel HEX with byte addresses, high byte of each word first.
+    IntelHex,
+    /// One hex word per line for Verilog `$readmemh`.
+    ReadMemH,
+    /// One binary word per line for Verilog `$readmemb`.
+    ReadMemB,
+    /// Logisim `v2.0 raw` ROM contents.
+    Logisim,
+    /// Rust source with a `const ROM: [u16; N]`.
+    Rust,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 8] = [
+        OutputFormat::Hack,
+        OutputFormat::BinaryBigEndian,
+        OutputFormat::BinaryLittleEndian,
+        OutputFormat::IntelHex,
+        OutputFormat::ReadMemH,
+        OutputFormat::ReadMemB,
+        OutputFormat::Logisim,
+        OutputFormat::Rust,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Hack => "hack",
+            OutputFormat::BinaryBigEndian => "bin-be",
+            OutputFormat::BinaryLittleEndian => "bin-le",
+            OutputFormat::IntelHex => "ihex",
+            OutputFormat::ReadMemH => "readmemh",
+            OutputFormat::ReadMemB => "readmemb",
+            OutputFormat::Logisim => "logisim",
+            OutputFormat::Rust => "rust",
+        }
+    }
+
+    /// File extension used when no output file is given.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Hack => "hack",
+            OutputFormat::BinaryBigEndian | OutputFormat::BinaryLittleEndian => "bin",
+            OutputFormat::IntelHex => "hex",
+            OutputFormat::ReadMemH => "memh",
+            OutputFormat::ReadMemB => "memb",
+            OutputFormat::Logisim => "rom",
+            OutputFormat::Rust => "rs",
+        }
+    }
+
+    pub fn write(&self, instructions: &[Instruction], writer: &mut impl Write) -> io::Result<()> {
+        let words = instructions.iter().map(|x| x.0).collect::<Vec<_>>();
+        self.write_words("ROM", &words, writer)
+    }
+
+    /// Writes an initial RAM image, see [`Context::ram_image`](super::Context::ram_image).
+    pub fn write_ram(&self, words: &[u16], writer: &mut impl Write) -> io::Result<()> {
+        self.write_words("RAM", words, writer)
+    }
+
+    /// `name` is only used by formats that declare the memory they hold.
+    fn write_words(&self, name: &str, words: &[u16], writer: &mut impl Write) -> io::Result<()> {
+        match self {
+            OutputFormat::Hack | OutputFormat::ReadMemB => {
+                for word in words {
+                    writeln!(writer, "{:016b}", word)?;
+                }
+            }
+            OutputFormat::BinaryBigEndian => {
+                for word in words {
+                    writer.write_all(&word.to_be_bytes())?;
+                }
+            }
+            OutputFormat::BinaryLittleEndian => {
+                for word in words {
+                    writer.write_all(&word.to_le_bytes())?;
+                }
+            }
+            OutputFormat::IntelHex => write_intel_hex(words, writer)?,
+            OutputFormat::ReadMemH => {
+                for word in words {
+                    writeln!(writer, "{:04x}", word)?;
+                }
+            }
+            OutputFormat::Logisim => {
+                writeln!(writer, "v2.0 raw")?;
+                for chunk in words.chunks(8) {
+                    let words = chunk.iter().map(|x| format!("{:04x}", x)).collect::<Vec<_>>();
+                    writeln!(writer, "{}", words.join(" "))?;
+                }
+            }
+            OutputFormat::Rust => {
+                writeln!(writer, "pub const {}: [u16; {}] = [", name, words.len())?;
+                for word in words {
+                    writeln!(writer, "    0b{:016b},", word)?;
+                }
+                writeln!(writer, "];")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OutputFormat::ALL.iter()
+            .find(|x| x.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names = OutputFormat::ALL.iter().map(|x| x.name()).collect::<Vec<_>>();
+                format!("Unknown output format {}, expected one of {}", s, names.join(", "))
+            })
+    }
+}
+
+/// Data records of 16 bytes followed by the end-of-file record.
+/// 32K words take exactly the 64K bytes a 16-bit record address can reach, larger images such as
+/// a full RAM continue after an extended linear address record with the upper address bits.
+fn write_intel_hex(words: &[u16], writer: &mut impl Write) -> io::Result<()> {
+    const WORDS_PER_RECORD: usize = 8;
+
+    for (idx, chunk) in words.chunks(WORDS_PER_RECORD).enumerate() {
+        let address = idx * WORDS_PER_RECORD * 2;
+        if address > 0 && address.is_multiple_of(0x10000) {
+            write_hex_record(writer, 0, 0x04, &((address >> 16) as u16).to_be_bytes())?;
+        }
+        let data = chunk.iter().flat_map(|x| x.to_be_bytes()).collect::<Vec<_>>();
+        write_hex_record(writer, address as u16, 0x00, &data)?;
+    }
+    writeln!(writer, ":00000001FF")
+}
+
+fn write_hex_record(writer: &mut impl Write, address: u16, kind: u8, data: &[u8]) -> io::Result<()> {
+    let mut record = vec![data.len() as u8];
+    record.extend(address.to_be_bytes());
+    record.push(kind);
+    record.extend(data);
+    let checksum = record.iter().fold(0u8, |acc, x| acc.wrapping_add(*x)).wrapping_neg();
+    write!(writer, ":")?;
+    for byte in record {
+        write!(writer, "{:02X}", byte)?;
+    }
+    writeln!(writer, "{:02X}", checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(format: OutputFormat, words: &[u16]) -> Vec<u8> {
+        let instructions = words.iter().map(|x| Instruction(*x)).collect::<Vec<_>>();
+        let mut output = Vec::new();
+        format.write(&instructions, &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn binary() {
+        assert_eq!(render(OutputFormat::BinaryBigEndian, &[0x0002, 0xEC10]), [0x00, 0x02, 0xEC, 0x10]);
+        assert_eq!(render(OutputFormat::BinaryLittleEndian, &[0x0002, 0xEC10]), [0x02, 0x00, 0x10, 0xEC]);
+    }
+
+    #[test]
+    fn intel_hex() {
+        let output = render(OutputFormat::IntelHex, &[0x0002, 0xEC10, 0x0003, 0xE090, 0x0000, 0xE308]);
+        assert_eq!(String::from_utf8(output).unwrap(), ":0C0000000002EC100003E0900000E30898\n:00000001FF\n");
+
+        // past 32K words the records continue in the next 64K byte segment
+        let mut words = vec![0; 0x8000];
+        words.push(5);
+        let mut output = Vec::new();
+        OutputFormat::IntelHex.write_ram(&words, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with(":020000040001F9\n:020000000005F9\n:00000001FF\n"), "{}", output);
+    }
+
+    #[test]
+    fn text_formats() {
+        let words = [0x0002, 0xEC10];
+        assert_eq!(String::from_utf8(render(OutputFormat::ReadMemH, &words)).unwrap(), "0002\nec10\n");
+        assert_eq!(String::from_utf8(render(OutputFormat::Logisim, &words)).unwrap(), "v2.0 raw\n0002 ec10\n");
+        assert_eq!(
+            String::from_utf8(render(OutputFormat::Rust, &words)).unwrap(),
+            "pub const ROM: [u16; 2] = [\n    0b0000000000000010,\n    0b1110110000010000,\n];\n"
+        );
+
+        let mut output = Vec::new();
+        OutputFormat::Rust.write_ram(&[7], &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "pub const RAM: [u16; 1] = [\n    0b0000000000000111,\n];\n");
+    }
+}
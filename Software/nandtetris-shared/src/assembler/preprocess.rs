@@ -0,0 +1,613 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::ops::Range;
+use super::statement::{Statement, STATEMENT_DIRECTIVES};
+use super::{
+    is_symbol, strip_comment, Comp, Error, ErrorKind, Expression, Location, LocationKind, SymbolKind, SymbolTable,
+    ROM_SIZE,
+};
+
+/// Upper bound for `.rept` counts, enough to fill the whole ROM.
+pub const MAX_REPEAT: i64 = 0x8000;
+
+/// Upper bound for the lines produced by all `.rept` and macro expansions of a source together,
+/// so nested blocks cannot multiply their counts past what the ROM could ever hold.
+/// Leaves room for labels, constants and comments next to a full ROM of instructions.
+pub const MAX_EXPANDED_LINES: usize = 4 * ROM_SIZE;
+
+/// An input to the assembler. `name` is used in diagnostics and to resolve relative includes.
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub name: Option<Rc<str>>,
+    pub content: String,
+}
+
+impl Source {
+    pub fn new(content: impl Into<String>) -> Self {
+        Source { name: None, content: content.into() }
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        Ok(Source {
+            name: Some(path.to_string_lossy().into()),
+            content: std::fs::read_to_string(path)?,
+        })
+    }
+}
+
+/// A line of code after preprocessing, still in source form.
+#[derive(Debug, Clone)]
+pub(crate) struct SourceText {
+    pub file: Option<Rc<str>>,
+    pub line: usize,
+    pub text: String,
+    /// Macro call sites and includes, innermost first.
+    pub expanded_from: Vec<Location>,
+}
+
+/// A `.macro NAME params ... .endm` block.
+///
+/// Parameters are referenced as `\name` in the body. Labels defined in the body are renamed
+/// to `label$n` on the n-th expansion, so a macro can be used more than once.
+#[derive(Debug)]
+struct Macro {
+    file: Option<Rc<str>>,
+    params: Vec<String>,
+    body: Vec<(usize, String)>,
+    labels: Vec<String>,
+}
+
+impl Macro {
+    fn substitute(&self, text: &str, args: &[&str], expansion: usize) -> String {
+        let mut text = text.to_string();
+        let mut params = self.params.iter().zip(args).collect::<Vec<_>>();
+        // longest first, so `\ab` is not replaced as `\a` followed by `b`
+        params.sort_by_key(|(param, _)| std::cmp::Reverse(param.len()));
+        for (param, arg) in params {
+            text = text.replace(&format!("\\{}", param), arg);
+        }
+
+        let (offset, code) = strip_comment(&text);
+        let renamed = self.labels.iter().find_map(|label| {
+            if code.strip_prefix('(').and_then(|x| x.strip_suffix(')')) == Some(label) {
+                Some(format!("({}${})", label, expansion))
+            } else if code.strip_prefix('@') == Some(label) {
+                Some(format!("@{}${}", label, expansion))
+            } else {
+                None
+            }
+        });
+        if let Some(renamed) = renamed {
+            text.replace_range(offset..offset + code.len(), &renamed);
+        }
+        text
+    }
+}
+
+/// Splits a directive or macro call into its words, arguments may be separated by commas.
+fn words(code: &str) -> Vec<&str> {
+    code.split(|c: char| c.is_whitespace() || c == ',').filter(|x| !x.is_empty()).collect()
+}
+
+/// Labels defined in a macro or `.rept` body, these are renamed on every expansion.
+fn body_labels(body: &[(usize, String)]) -> Vec<String> {
+    body.iter()
+        .filter_map(|(_, text)| {
+            let (_, code) = strip_comment(text);
+            code.strip_prefix('(')?.strip_suffix(')').map(str::to_string)
+        })
+        .collect()
+}
+
+/// Reads the lines of a `.rept` block up to its `.endr`, `None` if it is missing.
+fn repeat_body<'a>(lines: &mut impl Iterator<Item = (usize, &'a str)>) -> Option<Vec<(usize, String)>> {
+    let mut depth = 0;
+    let mut body = Vec::new();
+    for (line, text) in lines.by_ref() {
+        match strip_comment(text).1.split_whitespace().next() {
+            Some(".rept") => depth += 1,
+            Some(".endr") if depth == 0 => return Some(body),
+            Some(".endr") => depth -= 1,
+            _ => {}
+        }
+        body.push((line, text.to_string()));
+    }
+    None
+}
+
+fn evaluate(text: &str, constants: &SymbolTable) -> Result<i64, ErrorKind> {
+    let expression = Expression::parse(text).ok_or_else(|| ErrorKind::InvalidExpression(text.to_string()))?;
+    expression.evaluate(constants)
+}
+
+/// Evaluates the argument of `.if`, `.ifdef` or `.ifndef`.
+fn condition(directive: &str, code: &str, constants: &SymbolTable) -> Result<bool, ErrorKind> {
+    let argument = code[directive.len()..].trim();
+    if directive == ".if" {
+        return Ok(evaluate(argument, constants)? != 0);
+    }
+    if !is_symbol(argument) {
+        return Err(ErrorKind::InvalidSymbol(argument.to_string()));
+    }
+    Ok(constants.get(argument).is_some() == (directive == ".ifdef"))
+}
+
+/// An open `.if` block.
+struct Conditional {
+    line: usize,
+    span: Range<usize>,
+    /// Whether lines of the current branch are used.
+    active: bool,
+    /// Whether the condition held, the `.else` branch is used otherwise.
+    taken: bool,
+    /// Whether the enclosing block is active.
+    parent: bool,
+    else_seen: bool,
+}
+
+/// Expands macros, includes, conditionals and repeat blocks before the lines are parsed into `CodeLine`s.
+///
+/// `.if expr`, `.ifdef NAME` and `.ifndef NAME` see predefined symbols, symbols defined before
+/// assembling (e.g. `-D DEBUG=1`) and `.equ` constants defined above them, but no labels.
+#[derive(Debug, Default)]
+pub(crate) struct Preprocessor {
+    macros: HashMap<String, Rc<Macro>>,
+    expansions: usize,
+}
+
+/// State of one preprocessor run over a top-level source.
+struct Run<'a> {
+    include_paths: &'a [PathBuf],
+    /// Canonical paths of the files being read, to catch include cycles.
+    stack: Vec<PathBuf>,
+    output: Vec<SourceText>,
+    errors: &'a mut Vec<Error>,
+    /// Symbols conditions can refer to.
+    constants: &'a mut SymbolTable,
+    /// Lines expanded so far, limited by `MAX_EXPANDED_LINES`.
+    expanded: usize,
+    /// Set once the limit is hit, later expansions are skipped.
+    exhausted: bool,
+}
+
+impl Preprocessor {
+    /// Returns the lines to parse, problems are added to `errors` so parsing can still report its own.
+    /// `.equ` constants seen in the source are added to `constants`.
+    pub fn run(
+        &mut self,
+        source: &Source,
+        include_paths: &[PathBuf],
+        constants: &mut SymbolTable,
+        errors: &mut Vec<Error>,
+    ) -> Vec<SourceText> {
+        let mut run = Run {
+            include_paths,
+            stack: Vec::new(),
+            output: Vec::new(),
+            errors,
+            constants,
+            expanded: 0,
+            exhausted: false,
+        };
+        if let Some(path) = source.name.as_deref().and_then(|x| Path::new(x).canonicalize().ok()) {
+            run.stack.push(path);
+        }
+        self.run_file(&source.name, &source.content, &[], &mut Vec::new(), &mut run);
+        run.output
+    }
+
+    fn run_file(
+        &mut self,
+        file: &Option<Rc<str>>,
+        content: &str,
+        chain: &[Location],
+        active: &mut Vec<String>,
+        run: &mut Run,
+    ) {
+        let lines = content.lines().enumerate().map(|(idx, line)| (idx + 1, line.to_string())).collect::<Vec<_>>();
+        self.run_lines(file, &lines, chain, active, run);
+    }
+
+    /// Handles the directives of a file or an expanded body. Conditionals must be closed within it.
+    fn run_lines(
+        &mut self,
+        file: &Option<Rc<str>>,
+        lines: &[(usize, String)],
+        chain: &[Location],
+        active: &mut Vec<String>,
+        run: &mut Run,
+    ) {
+        let mut conditionals: Vec<Conditional> = Vec::new();
+        let mut lines = lines.iter().map(|(line, text)| (*line, text.as_str()));
+        while let Some((line, text)) = lines.next() {
+            let (offset, code) = strip_comment(text);
+            let error = |kind| {
+                Error::new(line, offset..offset + code.len(), kind).with_file(file.clone()).with_expansion(chain)
+            };
+            let enabled = conditionals.last().is_none_or(|x| x.active);
+            match words(code).first().copied() {
+                None => {}
+                Some(directive @ (".if" | ".ifdef" | ".ifndef")) => {
+                    let taken = enabled && condition(directive, code, run.constants).unwrap_or_else(|kind| {
+                        run.errors.push(error(kind));
+                        false
+                    });
+                    let span = offset..offset + code.len();
+                    conditionals.push(Conditional { line, span, active: taken, taken, parent: enabled, else_seen: false });
+                }
+                Some(".else") => match conditionals.last_mut() {
+                    Some(conditional) if !conditional.else_seen => {
+                        conditional.else_seen = true;
+                        conditional.active = conditional.parent && !conditional.taken;
+                    }
+                    Some(_) => run.errors.push(error(ErrorKind::DuplicateElse)),
+                    None => run.errors.push(error(ErrorKind::UnexpectedDirective { directive: ".else", opening: ".if" })),
+                },
+                // the guard closes the innermost `.if`
+                Some(".endif") if conditionals.pop().is_none() => {
+                    run.errors.push(error(ErrorKind::UnexpectedDirective { directive: ".endif", opening: ".if" }));
+                }
+                Some(".endif") => {}
+                Some(_) if !enabled => {}
+                Some(".macro") => {
+                    if let Err(kind) = self.define(file, code, &mut lines) {
+                        run.errors.push(error(kind));
+                    }
+                }
+                Some(".endm") => run.errors.push(error(ErrorKind::UnexpectedEndMacro)),
+                Some(".rept") => {
+                    let Some(body) = repeat_body(&mut lines) else {
+                        run.errors.push(error(ErrorKind::UnterminatedBlock { directive: ".rept", closing: ".endr" }));
+                        continue;
+                    };
+                    let count = evaluate(code[".rept".len()..].trim(), run.constants).and_then(|count| {
+                        if (0..=MAX_REPEAT).contains(&count) {
+                            Ok(count)
+                        } else {
+                            Err(ErrorKind::InvalidRepeatCount(count))
+                        }
+                    });
+                    let count = match count {
+                        Ok(count) => count,
+                        Err(kind) => {
+                            run.errors.push(error(kind));
+                            continue;
+                        }
+                    };
+                    let labels = body_labels(&body);
+                    let block = Macro { file: file.clone(), params: Vec::new(), body, labels };
+                    let mut call_sites = vec![Location { file: file.clone(), line, kind: LocationKind::Repeat }];
+                    call_sites.extend_from_slice(chain);
+                    for _ in 0..count {
+                        if let Err(kind) = self.expand_body(&block, &[], &call_sites, active, run) {
+                            run.errors.push(error(kind));
+                            break;
+                        }
+                    }
+                }
+                Some(".endr") => {
+                    run.errors.push(error(ErrorKind::UnexpectedDirective { directive: ".endr", opening: ".rept" }));
+                }
+                Some(".include") => {
+                    let mut chain = chain.to_vec();
+                    chain.insert(0, Location { file: file.clone(), line, kind: LocationKind::Include });
+                    if let Err(kind) = self.include(file, code, &chain, active, run) {
+                        run.errors.push(error(kind));
+                    }
+                }
+                Some(_) => self.expand(file, line, text, chain, active, run),
+            }
+        }
+        for conditional in conditionals {
+            let kind = ErrorKind::UnterminatedBlock { directive: ".if", closing: ".endif" };
+            let error = Error::new(conditional.line, conditional.span, kind);
+            run.errors.push(error.with_file(file.clone()).with_expansion(chain));
+        }
+    }
+
+    fn include(
+        &mut self,
+        file: &Option<Rc<str>>,
+        code: &str,
+        chain: &[Location],
+        active: &mut Vec<String>,
+        run: &mut Run,
+    ) -> Result<(), ErrorKind> {
+        let name = code[".include".len()..].trim();
+        let name = name.strip_prefix('"').and_then(|x| x.strip_suffix('"')).ok_or(ErrorKind::InvalidInclude)?;
+        let base = file.as_deref().and_then(|x| Path::new(x).parent()).unwrap_or(Path::new(""));
+        let path = std::iter::once(base.join(name))
+            .chain(run.include_paths.iter().map(|x| x.join(name)))
+            .find(|x| x.is_file())
+            .ok_or_else(|| ErrorKind::IncludeNotFound(name.to_string()))?;
+        let canonical = path.canonicalize().map_err(|_| ErrorKind::IncludeNotFound(name.to_string()))?;
+        if run.stack.contains(&canonical) {
+            return Err(ErrorKind::IncludeCycle(name.to_string()));
+        }
+        let source = Source::read(&path).map_err(|_| ErrorKind::IncludeNotFound(name.to_string()))?;
+
+        run.stack.push(canonical);
+        self.run_file(&source.name, &source.content, chain, active, run);
+        run.stack.pop();
+        Ok(())
+    }
+
+    fn define<'a>(
+        &mut self,
+        file: &Option<Rc<str>>,
+        code: &str,
+        lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    ) -> Result<(), ErrorKind> {
+        let words = words(code);
+        let name = words.get(1).copied().unwrap_or_default();
+
+        let mut body = Vec::new();
+        let mut nested = false;
+        let mut terminated = false;
+        for (body_line, text) in lines.by_ref() {
+            let (_, body_code) = strip_comment(text);
+            match body_code.split_whitespace().next() {
+                Some(".endm") => {
+                    terminated = true;
+                    break;
+                }
+                Some(".macro") => nested = true,
+                _ => {}
+            }
+            body.push((body_line, text.to_string()));
+        }
+
+        if !terminated {
+            return Err(ErrorKind::UnterminatedMacro(name.to_string()));
+        }
+        if nested {
+            return Err(ErrorKind::NestedMacroDefinition);
+        }
+        if !is_symbol(name) || name.parse::<Comp>().is_ok() {
+            return Err(ErrorKind::InvalidMacroName(name.to_string()));
+        }
+        if let Some(param) = words[2..].iter().find(|x| !is_symbol(x)) {
+            return Err(ErrorKind::InvalidSymbol(param.to_string()));
+        }
+        if self.macros.contains_key(name) {
+            return Err(ErrorKind::DuplicateMacro(name.to_string()));
+        }
+
+        let labels = body_labels(&body);
+        let params = words[2..].iter().map(|x| x.to_string()).collect();
+        self.macros.insert(name.to_string(), Rc::new(Macro { file: file.clone(), params, body, labels }));
+        Ok(())
+    }
+
+    fn expand(
+        &mut self,
+        file: &Option<Rc<str>>,
+        line: usize,
+        text: &str,
+        expanded_from: &[Location],
+        active: &mut Vec<String>,
+        run: &mut Run,
+    ) {
+        let (offset, code) = strip_comment(text);
+        let words = words(code);
+        let Some(&name) = words.first() else {
+            return;
+        };
+        let error = |kind| {
+            Error::new(line, offset..offset + code.len(), kind).with_file(file.clone()).with_expansion(expanded_from)
+        };
+
+        if let Some(m) = self.macros.get(name).cloned() {
+            let args = &words[1..];
+            if args.len() != m.params.len() {
+                let kind = ErrorKind::MacroArgumentCount { name: name.to_string(), expected: m.params.len(), found: args.len() };
+                run.errors.push(error(kind));
+                return;
+            }
+            if active.iter().any(|x| x == name) {
+                run.errors.push(error(ErrorKind::MacroRecursion(name.to_string())));
+                return;
+            }
+            let mut call_sites = vec![Location { file: file.clone(), line, kind: LocationKind::MacroCall }];
+            call_sites.extend_from_slice(expanded_from);
+            active.push(name.to_string());
+            let expanded = self.expand_body(&m, args, &call_sites, active, run);
+            active.pop();
+            if let Err(kind) = expanded {
+                run.errors.push(error(kind));
+            }
+        } else if name.starts_with('.') && !STATEMENT_DIRECTIVES.contains(&name) {
+            run.errors.push(error(ErrorKind::UnknownDirective(name.to_string())));
+        } else {
+            if name == ".equ" {
+                Self::record_constant(line, text, run.constants);
+            }
+            run.output.push(SourceText {
+                file: file.clone(),
+                line,
+                text: text.to_string(),
+                expanded_from: expanded_from.to_vec(),
+            });
+        }
+    }
+
+    /// Fails for the expansion that exceeds `MAX_EXPANDED_LINES`, the ones after it are skipped silently.
+    fn expand_body(
+        &mut self,
+        m: &Macro,
+        args: &[&str],
+        call_sites: &[Location],
+        active: &mut Vec<String>,
+        run: &mut Run,
+    ) -> Result<(), ErrorKind> {
+        if run.exhausted {
+            return Ok(());
+        }
+        // empty bodies count too, otherwise nested empty blocks could loop for ever
+        run.expanded += m.body.len().max(1);
+        if run.expanded > MAX_EXPANDED_LINES {
+            run.exhausted = true;
+            return Err(ErrorKind::ExpansionLimit);
+        }
+        self.expansions += 1;
+        let body = m.body.iter()
+            .map(|(line, text)| (*line, m.substitute(text, args, self.expansions)))
+            .collect::<Vec<_>>();
+        self.run_lines(&m.file, &body, call_sites, active, run);
+        Ok(())
+    }
+
+    /// Makes a `.equ` constant visible to conditions below it.
+    /// Invalid definitions are left for the assembler to report.
+    fn record_constant(line: usize, text: &str, constants: &mut SymbolTable) {
+        let Ok(Some(Statement::Equ { name, value })) = Statement::parse(line, text) else {
+            return;
+        };
+        if let Some(value) = value.evaluate(constants).ok().and_then(|x| u16::try_from(x).ok()) {
+            let _ = constants.insert(name.into(), value, SymbolKind::Constant);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembler::{assemble, Context, Source, SymbolKind};
+
+    #[test]
+    fn expands_macros() {
+        let input = "\
+.macro PUSH_CONST value
+    @\\value
+    D=A
+    @SP
+    AM=M+1
+    A=A-1
+    M=D
+.endm
+.macro WAIT
+(LOOP)
+    @LOOP
+    0;JMP
+.endm
+PUSH_CONST 7
+WAIT
+WAIT
+";
+        let expected = "@7\nD=A\n@SP\nAM=M+1\nA=A-1\nM=D\n(A)\n@A\n0;JMP\n(B)\n@B\n0;JMP\n";
+        assert_eq!(assemble(input), assemble(expected));
+    }
+
+    #[test]
+    fn reports_call_site() {
+        let input = ".macro INNER\nD=Q\n.endm\n.macro OUTER x\nINNER\n.endm\nOUTER 1\nOUTER\n.endm\n.foo\n";
+        let errors = assemble(input).unwrap_err().to_string();
+        assert_eq!(errors.lines().collect::<Vec<_>>(), [
+            "2:3: unknown comp `Q`, expanded from line 5, expanded from line 7",
+            "8:1: macro `OUTER` expects 1 argument(s), found 0",
+            "9:1: `.endm` without `.macro`",
+            "10:1: unknown directive `.foo`",
+        ]);
+    }
+
+    #[test]
+    fn rejects_recursion() {
+        let input = ".macro A1\nB1\n.endm\n.macro B1\nA1\n.endm\nA1\n";
+        let errors = assemble(input).unwrap_err().to_string();
+        assert_eq!(errors, "5:1: macro `A1` expands recursively, expanded from line 2, expanded from line 7");
+    }
+
+    #[test]
+    fn includes_files() {
+        let dir = std::env::temp_dir().join(format!("hack-include-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(dir.join("lib/mult.asm"), "(MULT)\n@R0\n").unwrap();
+        std::fs::write(dir.join("main.asm"), ".include \"mult.asm\"\n@MULT\n0;JMP\n").unwrap();
+        std::fs::write(dir.join("cycle.asm"), "@R1\n.include \"cycle.asm\"\n").unwrap();
+
+        let mut context = Context::default();
+        context.include_paths.push(dir.join("lib"));
+        let instructions = context.assemble_sources(&[Source::read(dir.join("main.asm")).unwrap()]);
+        assert_eq!(instructions, Context::default().assemble("(MULT)\n@R0\n@MULT\n0;JMP\n"));
+
+        let errors = Context::default().assemble_sources(&[Source::read(dir.join("cycle.asm")).unwrap()]);
+        let errors = errors.unwrap_err();
+        assert_eq!(errors.0[0].kind, crate::assembler::ErrorKind::IncludeCycle("cycle.asm".to_string()));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn shares_labels_between_sources() {
+        let sources = [
+            Source { name: Some("a.asm".into()), content: "(LOOP)\n@END\n".to_string() },
+            Source { name: Some("b.asm".into()), content: "(END)\n\n(LOOP)\n".to_string() },
+        ];
+        let errors = Context::default().assemble_sources(&sources).unwrap_err();
+        assert_eq!(errors.to_string(), "b.asm:3:1: label `LOOP` is already defined at a.asm:1 with address 0");
+    }
+
+    #[test]
+    fn conditionals() {
+        let input = "\
+.equ LEVEL 2
+.ifdef DEBUG
+    @R1
+    .if LEVEL-2
+        @R2
+    .else
+        @R3
+    .endif
+.else
+    .foo
+.endif
+.ifndef DEBUG
+    @R4
+.endif
+";
+        let mut context = Context::default();
+        context.symbol_table.insert("DEBUG".into(), 1, SymbolKind::Constant).unwrap();
+        assert_eq!(context.assemble(input), Context::default().assemble("@R1\n@R3\n"));
+        assert_eq!(Context::default().assemble(input).unwrap_err().to_string(), "10:5: unknown directive `.foo`");
+
+        let errors = assemble(".if NOPE\n.else\n.else\n.endif\n.endif\n.ifdef 1\n").unwrap_err().to_string();
+        assert_eq!(errors.lines().collect::<Vec<_>>(), [
+            "1:1: undefined symbol `NOPE` in expression",
+            "3:1: `.if` already has an `.else`",
+            "5:1: `.endif` without `.if`",
+            "6:1: invalid symbol `1`",
+            "6:1: `.if` is missing `.endif`",
+        ]);
+    }
+
+    #[test]
+    fn repeats() {
+        let input = ".equ N 2\n.rept N\n(WAIT)\n@WAIT\n.rept 2\nM=-1\n.endr\n.endr\n.rept 0\nD=Q\n.endr\n";
+        let expected = "(A)\n@A\nM=-1\nM=-1\n(B)\n@B\nM=-1\nM=-1\n";
+        assert_eq!(assemble(input), assemble(expected));
+
+        let errors = assemble(".rept 2\nD=Q\n.endr\n.endr\n.rept -1\n.endr\n.rept 1\n").unwrap_err().to_string();
+        assert_eq!(errors.lines().collect::<Vec<_>>(), [
+            "2:3: unknown comp `Q`, repeated from line 1",
+            "2:3: unknown comp `Q`, repeated from line 1",
+            "4:1: `.endr` without `.rept`",
+            "5:1: repeat count -1 must be between 0 and 32768",
+            "7:1: `.rept` is missing `.endr`",
+        ]);
+    }
+
+    #[test]
+    fn limits_total_expansion() {
+        let nested = ".rept 0x8000\n.rept 0x8000\nD=0\n.endr\n.endr\n";
+        let errors = assemble(nested).unwrap_err().to_string();
+        assert_eq!(errors, "2:1: expansions produce more than 131072 lines, repeated from line 1");
+
+        let empty = ".rept 0x8000\n.rept 0x8000\n.endr\n.endr\n";
+        assert!(assemble(empty).unwrap_err().to_string().contains("expansions produce more than"));
+
+        let macros = ".macro FILL\n.rept 0x8000\nD=0\n.endr\n.endm\n.rept 8\nFILL\n.endr\n";
+        let errors = assemble(macros).unwrap_err().to_string();
+        assert!(errors.starts_with("2:1: expansions produce more than 131072 lines"), "{}", errors);
+    }
+}
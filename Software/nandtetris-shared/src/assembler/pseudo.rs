@@ -0,0 +1,143 @@
+use super::predefined_symbols::SP;
+use super::{is_literal, parse_literal, strip_comment, CodeLine, Comp, Dest, Error, ErrorKind, Jump, MAX_A_VALUE};
+
+/// Prefix of the return labels generated by `CALL`, they are local to the module.
+pub(crate) const RETURN_LABEL_PREFIX: &str = "$ret.";
+
+/// Expands a pseudo-instruction such as `JEQ D, LOOP` or `PUSHD` into instructions.
+/// Returns `None` for lines that are not pseudo-instructions. `calls` numbers the return labels
+/// of `CALL` and is incremented for each one.
+///
+/// `CALL` pushes the return address onto the stack at `SP` and overwrites D, `RET` pops it.
+pub(crate) fn expand(line_number: usize, line: &str, calls: &mut usize) -> Result<Option<Vec<CodeLine>>, Error> {
+    let (offset, code) = strip_comment(line);
+    let mnemonic_end = code.find(char::is_whitespace).unwrap_or(code.len());
+    let (mnemonic, rest) = code.split_at(mnemonic_end);
+    let operands = match rest.trim() {
+        "" => Vec::new(),
+        rest => rest.split(',').map(str::trim).collect::<Vec<_>>(),
+    };
+    let error = |kind| Error::new(line_number, offset..offset + code.len(), kind);
+    let usage = |usage| error(ErrorKind::PseudoUsage(usage));
+    let address = |operand: &str| match CodeLine::parse(line_number, &format!("@{}", operand)) {
+        Ok(Some(CodeLine::A(address))) => Ok(address),
+        Ok(_) => Err(error(ErrorKind::InvalidSymbol(operand.to_string()))),
+        Err(e) => Err(error(e.kind)),
+    };
+    // A holds the operand address when the comp is evaluated
+    let comp = |operand: &str| match operand.parse::<Comp>() {
+        Ok(comp) if !comp.reads_a() && !comp.reads_m() => Ok(comp),
+        Ok(_) => Err(error(ErrorKind::OverwrittenRegister(operand.to_string()))),
+        Err(_) => Err(error(ErrorKind::UnknownComp(operand.to_string()))),
+    };
+
+    let lines = match (mnemonic, &operands[..]) {
+        ("JMP", [label]) => vec![CodeLine::A(address(label)?), CodeLine::goto()],
+        ("JMP", _) => return Err(usage("JMP <label>")),
+        ("JGT" | "JEQ" | "JGE" | "JLT" | "JNE" | "JLE", operands) => {
+            let [value, label] = operands else {
+                return Err(usage("J<condition> <comp>, <label>"));
+            };
+            let jump = mnemonic.parse::<Jump>().expect("Mnemonic is a jump");
+            vec![CodeLine::A(address(label)?), CodeLine::test(Dest::default(), comp(value)?, jump)]
+        }
+        ("LOAD", ["A", value]) => vec![CodeLine::A(address(value)?)],
+        ("LOAD", ["D", value]) => match parse_literal(value).filter(|_| is_literal(value)) {
+            Some(0) => vec![CodeLine::assign(Dest::D, Comp::Zero)],
+            Some(1) => vec![CodeLine::assign(Dest::D, Comp::One)],
+            Some(-1) => vec![CodeLine::assign(Dest::D, Comp::NegOne)],
+            Some(value) if (-i64::from(MAX_A_VALUE)..=-2).contains(&value) => {
+                vec![CodeLine::constant(-value as u16), CodeLine::assign(Dest::D, Comp::NegA)]
+            }
+            _ => vec![CodeLine::A(address(value)?), CodeLine::assign(Dest::D, Comp::A)],
+        },
+        ("LOAD", _) => return Err(usage("LOAD <A|D>, <value>")),
+        ("STORE", [target, value]) => vec![CodeLine::A(address(target)?), CodeLine::assign(Dest::M, comp(value)?)],
+        ("STORE", _) => return Err(usage("STORE <address>, <comp>")),
+        ("INC", [target]) => vec![CodeLine::A(address(target)?), CodeLine::assign(Dest::M, Comp::MPlusOne)],
+        ("INC", _) => return Err(usage("INC <address>")),
+        ("DEC", [target]) => vec![CodeLine::A(address(target)?), CodeLine::assign(Dest::M, Comp::MMinusOne)],
+        ("DEC", _) => return Err(usage("DEC <address>")),
+        ("PUSHD", []) => push_d(),
+        ("PUSHD", _) => return Err(usage("PUSHD")),
+        ("POPD", []) => {
+            vec![SP.into(), CodeLine::assign(Dest::AM, Comp::MMinusOne), CodeLine::assign(Dest::D, Comp::M)]
+        }
+        ("POPD", _) => return Err(usage("POPD")),
+        ("CALL", [label]) => {
+            let target = address(label)?;
+            let return_label = format!("{}{}", RETURN_LABEL_PREFIX, calls);
+            *calls += 1;
+            let mut lines = vec![CodeLine::variable(return_label.clone()), CodeLine::assign(Dest::D, Comp::A)];
+            lines.extend(push_d());
+            lines.extend([CodeLine::A(target), CodeLine::goto(), CodeLine::Label(return_label)]);
+            lines
+        }
+        ("CALL", _) => return Err(usage("CALL <label>")),
+        ("RET", []) => vec![
+            SP.into(),
+            CodeLine::assign(Dest::AM, Comp::MMinusOne),
+            CodeLine::assign(Dest::A, Comp::M),
+            CodeLine::goto(),
+        ],
+        ("RET", _) => return Err(usage("RET")),
+        _ => return Ok(None),
+    };
+    Ok(Some(lines))
+}
+
+fn push_d() -> Vec<CodeLine> {
+    vec![
+        SP.into(),
+        CodeLine::assign(Dest::M, Comp::MPlusOne),
+        CodeLine::assign(Dest::A, Comp::MMinusOne),
+        CodeLine::assign(Dest::M, Comp::D),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expanded(line: &str) -> Vec<String> {
+        let lines = expand(1, line, &mut 0).unwrap().unwrap();
+        lines.iter().map(|x| x.to_string()).collect()
+    }
+
+    fn error(line: &str) -> String {
+        expand(1, line, &mut 0).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn expands_pseudo_instructions() {
+        assert_eq!(expanded("JMP LOOP"), ["@LOOP", "0;JMP"]);
+        assert_eq!(expanded("  JNE D-1, END // loop"), ["@END", "D-1;JNE"]);
+        assert_eq!(expanded("LOAD D, -1"), ["D=-1"]);
+        assert_eq!(expanded("LOAD D, -5"), ["@5", "D=-A"]);
+        assert_eq!(expanded("LOAD D, SCREEN+32"), ["@SCREEN+32", "D=A"]);
+        assert_eq!(expanded("STORE R2, 0"), ["@R2", "M=0"]);
+        assert_eq!(expanded("INC i"), ["@i", "M=M+1"]);
+        assert_eq!(expanded("PUSHD"), ["@SP", "M=M+1", "A=M-1", "M=D"]);
+        assert_eq!(expanded("POPD"), ["@SP", "AM=M-1", "D=M"]);
+        assert_eq!(expanded("RET"), ["@SP", "AM=M-1", "A=M", "0;JMP"]);
+
+        let mut calls = 3;
+        let lines = expand(1, "CALL MULT", &mut calls).unwrap().unwrap();
+        let lines = lines.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(lines, ["@$ret.3", "D=A", "@SP", "M=M+1", "A=M-1", "M=D", "@MULT", "0;JMP", "($ret.3)"]);
+        assert_eq!(calls, 4);
+
+        assert_eq!(expand(1, "D=M", &mut 0), Ok(None));
+        assert_eq!(expand(1, "0;JMP", &mut 0), Ok(None));
+    }
+
+    #[test]
+    fn rejects_bad_operands() {
+        assert_eq!(error("JEQ M, END"), "1:1: comp `M` reads A or M, which the pseudo-instruction overwrites");
+        assert_eq!(error("JEQ END"), "1:1: expected `J<condition> <comp>, <label>`");
+        assert_eq!(error("  LOAD M, 1"), "1:3: expected `LOAD <A|D>, <value>`");
+        assert_eq!(error("STORE x, Q"), "1:1: unknown comp `Q`");
+        assert_eq!(error("INC 1abc"), "1:1: invalid number `1abc`");
+        assert_eq!(error("PUSHD D"), "1:1: expected `PUSHD`");
+    }
+}
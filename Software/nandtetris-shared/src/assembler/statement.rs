@@ -0,0 +1,83 @@
+use super::{is_symbol, strip_comment, CodeLine, Error, ErrorKind, Expression, Term};
+
+/// Directives that are handled after preprocessing, when symbols can be resolved.
+pub(crate) const STATEMENT_DIRECTIVES: &[&str] = &[".equ", ".word", ".string", ".data"];
+
+/// Initial contents of RAM cells reserved by a data directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Cells {
+    /// One cell per value.
+    Words(Vec<Expression>),
+    /// The given number of cells, all zero.
+    Zeroed(Expression),
+}
+
+/// A parsed source line: an instruction or a directive that takes part in symbol resolution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Statement {
+    Code(CodeLine),
+    /// `.equ NAME value`, a named constant.
+    Equ { name: String, value: Expression },
+    /// `.word NAME v, ...`, `.string NAME "text"` or `.data NAME count`, named RAM cells.
+    Data { name: String, cells: Cells },
+}
+
+impl Statement {
+    pub fn parse(line_number: usize, line: &str) -> Result<Option<Self>, Error> {
+        let (offset, code) = strip_comment(line);
+        let directive_end = code.find(char::is_whitespace).unwrap_or(code.len());
+        let (directive, rest) = code.split_at(directive_end);
+        if !STATEMENT_DIRECTIVES.contains(&directive) {
+            return Ok(CodeLine::parse(line_number, line)?.map(Statement::Code));
+        }
+        let error = |kind| Error::new(line_number, offset..offset + code.len(), kind);
+
+        let rest = rest.trim_start();
+        let name_end = rest.find(|c: char| c.is_whitespace() || c == ',').unwrap_or(rest.len());
+        let (name, value) = rest.split_at(name_end);
+        let value = value.trim_start().trim_start_matches(',').trim();
+        if !is_symbol(name) {
+            return Err(error(ErrorKind::InvalidSymbol(name.to_string())));
+        }
+        let name = name.to_string();
+        let expression = |value: &str| {
+            Expression::parse(value.trim()).ok_or_else(|| error(ErrorKind::InvalidExpression(value.trim().to_string())))
+        };
+
+        Ok(Some(match directive {
+            ".equ" => Statement::Equ { name, value: expression(value)? },
+            ".word" => {
+                let values = value.split(',').map(expression).collect::<Result<_, _>>()?;
+                Statement::Data { name, cells: Cells::Words(values) }
+            }
+            ".string" => {
+                let chars = parse_string(value).ok_or_else(|| error(ErrorKind::InvalidString(value.to_string())))?;
+                let values = chars.into_iter()
+                    .chain(std::iter::once(0))
+                    .map(|x| Expression { terms: vec![(false, Term::Number(x))] })
+                    .collect();
+                Statement::Data { name, cells: Cells::Words(values) }
+            }
+            _ => Statement::Data { name, cells: Cells::Zeroed(expression(value)?) },
+        }))
+    }
+}
+
+/// Character codes of a double-quoted string, which may contain `\"`, `\\` and `\n`.
+fn parse_string(s: &str) -> Option<Vec<i64>> {
+    let mut chars = s.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut result = Vec::new();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next()? {
+                'n' => '\n',
+                c @ ('"' | '\\') => c,
+                _ => return None,
+            },
+            '"' => return None,
+            c => c,
+        };
+        result.push(i64::from(u32::from(c)));
+    }
+    Some(result)
+}
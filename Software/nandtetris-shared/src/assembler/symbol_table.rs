@@ -0,0 +1,275 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::str::FromStr;
+use super::PREDEFINED_SYMBOLS;
+
+/// Number of RAM cells the Hack platform can address.
+const RAM_SIZE: u32 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SymbolKind {
+    Predefined,
+    Label,
+    /// Defined with `.equ`.
+    Constant,
+    /// RAM cells reserved with `.word`, `.string` or `.data`.
+    Data,
+    Variable,
+}
+
+impl SymbolKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolKind::Predefined => "predefined",
+            SymbolKind::Label => "label",
+            SymbolKind::Constant => "constant",
+            SymbolKind::Data => "data",
+            SymbolKind::Variable => "variable",
+        }
+    }
+}
+
+impl FromStr for SymbolKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "predefined" => Ok(SymbolKind::Predefined),
+            "label" => Ok(SymbolKind::Label),
+            "constant" => Ok(SymbolKind::Constant),
+            "data" => Ok(SymbolKind::Data),
+            "variable" => Ok(SymbolKind::Variable),
+            _ => Err("Invalid SymbolKind string"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub address: u16,
+    pub kind: SymbolKind,
+}
+
+/// Why [`SymbolTable::allocate`] or [`SymbolTable::get_or_insert`] could not reserve RAM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationError {
+    /// The symbol is already defined, holds its address.
+    AlreadyDefined(u16),
+    /// The cells do not fit before the end of RAM.
+    RamExhausted,
+}
+
+/// Maps symbols to addresses. Starts with the predefined symbols, labels are inserted
+/// explicitly and unknown variables get RAM cells allocated from address 16 upward.
+#[derive(Debug, Clone)]
+pub struct SymbolTable {
+    symbols: HashMap<Cow<'static, str>, Symbol>,
+    /// Number of cells of each data block.
+    sizes: HashMap<Cow<'static, str>, u16>,
+    /// Reaches `RAM_SIZE` once every cell is taken.
+    next_address: u32,
+}
+
+impl SymbolTable {
+    pub fn get(&self, symbol: &str) -> Option<u16> {
+        self.symbols.get(symbol).map(|x| x.address)
+    }
+
+    pub fn get_symbol(&self, symbol: &str) -> Option<Symbol> {
+        self.symbols.get(symbol).copied()
+    }
+
+    /// Returns the address of `variable`, allocating the next free RAM cell if it is not defined yet.
+    pub fn get_or_insert(&mut self, variable: Cow<'static, str>) -> Result<u16, AllocationError> {
+        if let Some(symbol) = self.symbols.get(&variable) {
+            return Ok(symbol.address);
+        }
+        let address = u16::try_from(self.next_address).map_err(|_| AllocationError::RamExhausted)?;
+        self.next_address += 1;
+        self.symbols.insert(variable, Symbol { address, kind: SymbolKind::Variable });
+        Ok(address)
+    }
+
+    /// Adds a symbol with a fixed address, returns the existing address if it is already defined.
+    pub fn insert(&mut self, symbol: Cow<'static, str>, address: u16, kind: SymbolKind) -> Result<(), u16> {
+        match self.symbols.get(&symbol) {
+            Some(existing) => Err(existing.address),
+            None => {
+                self.symbols.insert(symbol, Symbol { address, kind });
+                Ok(())
+            }
+        }
+    }
+
+    /// Reserves `size` RAM cells for `symbol` at the next free address and returns it.
+    pub fn allocate(&mut self, symbol: Cow<'static, str>, size: u16) -> Result<u16, AllocationError> {
+        if let Some(existing) = self.symbols.get(&symbol) {
+            return Err(AllocationError::AlreadyDefined(existing.address));
+        }
+        let end = self.next_address + u32::from(size);
+        let address = u16::try_from(self.next_address)
+            .ok()
+            .filter(|_| end <= RAM_SIZE)
+            .ok_or(AllocationError::RamExhausted)?;
+        self.symbols.insert(symbol.clone(), Symbol { address, kind: SymbolKind::Data });
+        self.sizes.insert(symbol, size);
+        self.next_address = end;
+        Ok(address)
+    }
+
+    /// The RAM address the next new variable will get, 65536 once RAM is full.
+    pub fn next_address(&self) -> u32 {
+        self.next_address
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Symbol)> {
+        self.symbols.iter().map(|(name, &symbol)| (name.as_ref(), symbol))
+    }
+
+    /// Symbols ordered by kind, address and name, so exported files are stable.
+    pub fn sorted(&self) -> Vec<(&str, Symbol)> {
+        let mut symbols = self.iter().collect::<Vec<_>>();
+        symbols.sort_by(|(a_name, a), (b_name, b)| (a.kind, a.address, a_name).cmp(&(b.kind, b.address, b_name)));
+        symbols
+    }
+
+    /// Writes the `.sym` format: one `name kind address` line per symbol,
+    /// data blocks add their size as `name data address size`.
+    pub fn write_sym(&self, writer: &mut impl Write) -> io::Result<()> {
+        for (name, symbol) in self.sorted() {
+            write!(writer, "{} {} {}", name, symbol.kind.as_str(), symbol.address)?;
+            match self.sizes.get(name) {
+                Some(size) => writeln!(writer, " {}", size)?,
+                None => writeln!(writer)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a JSON array of `{"name", "kind", "address"}` objects.
+    pub fn write_json(&self, writer: &mut impl Write) -> io::Result<()> {
+        let symbols = self.sorted();
+        writeln!(writer, "[")?;
+        for (idx, (name, symbol)) in symbols.iter().enumerate() {
+            let separator = if idx + 1 < symbols.len() { "," } else { "" };
+            writeln!(
+                writer,
+                "  {{\"name\": \"{}\", \"kind\": \"{}\", \"address\": {}}}{}",
+                name.replace('\\', "\\\\").replace('"', "\\\""),
+                symbol.kind.as_str(),
+                symbol.address,
+                separator,
+            )?;
+        }
+        writeln!(writer, "]")
+    }
+
+    /// Reads a table back from the `.sym` format written by [`SymbolTable::write_sym`].
+    /// New variables continue after the highest imported variable or data block.
+    pub fn read_sym(content: &str) -> Result<Self, String> {
+        let mut table = SymbolTable::default();
+        for (idx, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            let error = |message: &str| format!("line {}: {}", idx + 1, message);
+            let parts = line.split_whitespace().collect::<Vec<_>>();
+            let (name, kind, address, size) = match parts[..] {
+                [name, "data", address, size] => (name, "data", address, Some(size)),
+                [_, "data", _] => return Err(error("expected `name data address size`")),
+                [name, kind, address] => (name, kind, address, None),
+                _ => return Err(error("expected `name kind address`")),
+            };
+            let kind = kind.parse::<SymbolKind>().map_err(error)?;
+            let address = address.parse::<u16>().map_err(|_| error("invalid address"))?;
+            let size = match size {
+                Some(size) => size.parse::<u16>().map_err(|_| error("invalid size"))?,
+                None => 1,
+            };
+            match kind {
+                SymbolKind::Predefined => {
+                    if table.get(name) != Some(address) {
+                        return Err(error("predefined symbol does not match the Hack platform"));
+                    }
+                }
+                _ => {
+                    table.insert(name.to_string().into(), address, kind)
+                        .map_err(|_| error("symbol is defined twice"))?;
+                    if kind == SymbolKind::Data {
+                        table.sizes.insert(name.to_string().into(), size);
+                    }
+                    if matches!(kind, SymbolKind::Variable | SymbolKind::Data) {
+                        let end = u32::from(address) + u32::from(size);
+                        if end > RAM_SIZE {
+                            return Err(error("data reaches past the end of RAM"));
+                        }
+                        table.next_address = table.next_address.max(end);
+                    }
+                }
+            }
+        }
+        Ok(table)
+    }
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        SymbolTable {
+            symbols: PREDEFINED_SYMBOLS.iter()
+                .map(|x| (x.name.into(), Symbol { address: x.value, kind: SymbolKind::Predefined }))
+                .collect(),
+            sizes: HashMap::new(),
+            next_address: 16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut table = SymbolTable::default();
+        table.insert("LOOP".into(), 4, SymbolKind::Label).unwrap();
+        table.get_or_insert("ball_x".into()).unwrap();
+        table.get_or_insert("ball_y".into()).unwrap();
+
+        let mut sym = Vec::new();
+        table.write_sym(&mut sym).unwrap();
+        let sym = String::from_utf8(sym).unwrap();
+        assert!(sym.ends_with("KBD predefined 24576\nLOOP label 4\nball_x variable 16\nball_y variable 17\n"));
+
+        let mut table = SymbolTable::read_sym(&sym).unwrap();
+        assert_eq!(table.get_symbol("LOOP"), Some(Symbol { address: 4, kind: SymbolKind::Label }));
+        assert_eq!(table.get_or_insert("ball_z".into()), Ok(18));
+
+        // new variables go after whole data blocks
+        let mut table = SymbolTable::default();
+        table.allocate("BUFFER".into(), 4).unwrap();
+        let mut sym = Vec::new();
+        table.write_sym(&mut sym).unwrap();
+        let sym = String::from_utf8(sym).unwrap();
+        assert!(sym.ends_with("BUFFER data 16 4\n"));
+        let mut table = SymbolTable::read_sym(&sym).unwrap();
+        assert_eq!(table.get_or_insert("x".into()), Ok(20));
+
+        let mut table = SymbolTable::read_sym("LAST variable 65535\n").unwrap();
+        assert_eq!(table.get_or_insert("x".into()), Err(AllocationError::RamExhausted));
+        assert_eq!(SymbolTable::read_sym("BUFFER data 16\n").unwrap_err(), "line 1: expected `name data address size`");
+        assert_eq!(SymbolTable::read_sym("BUFFER data 65535 2\n").unwrap_err(), "line 1: data reaches past the end of RAM");
+    }
+
+    #[test]
+    fn json() {
+        let mut table = SymbolTable::default();
+        table.get_or_insert("ball_x".into()).unwrap();
+        let mut json = Vec::new();
+        table.write_json(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.starts_with("[\n  {\"name\": \"R0\", \"kind\": \"predefined\", \"address\": 0},\n"));
+        assert!(json.ends_with("  {\"name\": \"ball_x\", \"kind\": \"variable\", \"address\": 16}\n]\n"));
+    }
+}
@@ -0,0 +1,30 @@
+use super::predefined_symbols::SCREEN;
+use super::{SymbolTable, ROM_SIZE};
+
+/// First RAM address available for variables, after `R0`..`R15`.
+const VARIABLE_BASE: u16 = 16;
+
+/// How much of the ROM and of the RAM below `SCREEN` an assembled program takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    /// Instructions.
+    pub rom: usize,
+    /// Cells taken by variables and data.
+    pub ram: usize,
+}
+
+impl Usage {
+    pub fn new(instructions: usize, symbol_table: &SymbolTable) -> Self {
+        let ram = symbol_table.next_address().saturating_sub(u32::from(VARIABLE_BASE));
+        Usage { rom: instructions, ram: ram as usize }
+    }
+}
+
+impl std::fmt::Display for Usage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let ram_size = usize::from(SCREEN.value - VARIABLE_BASE);
+        let percent = |used: usize, size: usize| used as f64 * 100.0 / size as f64;
+        writeln!(f, "ROM: {} of {} words ({:.1}%)", self.rom, ROM_SIZE, percent(self.rom, ROM_SIZE))?;
+        write!(f, "RAM: {} of {} words for variables and data ({:.1}%)", self.ram, ram_size, percent(self.ram, ram_size))
+    }
+}
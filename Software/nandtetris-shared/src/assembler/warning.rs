@@ -0,0 +1,93 @@
+use std::ops::Range;
+use std::rc::Rc;
+use std::str::FromStr;
+use super::error::{write_expansion, RamOverflow};
+use super::Location;
+
+/// Whether a configurable check stops assembling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Severity {
+    #[default]
+    Warning,
+    Error,
+}
+
+impl FromStr for Severity {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err("Invalid Severity string, expected warning or error"),
+        }
+    }
+}
+
+/// A problem that does not stop assembling, positioned like [`Error`](super::Error).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub file: Option<Rc<str>>,
+    pub line: usize,
+    pub span: Range<usize>,
+    pub kind: WarningKind,
+    pub expanded_from: Vec<Location>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Instructions no path from the start of the program reaches.
+    UnreachableCode(usize),
+    UnusedLabel(String),
+    /// A jump that also reads or writes M, so the jump target is the RAM address.
+    MemoryJump(String),
+    /// A jump on an increment or decrement that is not stored anywhere.
+    DiscardedComputation(&'static str),
+    /// A variable spelled like a label or predefined symbol with different case.
+    CaseMismatch { variable: String, symbol: String },
+    /// A variable that appears only once, usually a typo.
+    SingleUse(String),
+    RamOverflow(RamOverflow),
+}
+
+impl Warning {
+    /// The top-level source position, like [`Error::root`](super::Error::root).
+    pub fn root(&self) -> (Option<&str>, usize) {
+        match self.expanded_from.last() {
+            Some(location) => (location.file.as_deref(), location.line),
+            None => (self.file.as_deref(), self.line),
+        }
+    }
+
+    pub fn column(&self) -> usize {
+        self.span.start + 1
+    }
+}
+
+impl std::fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WarningKind::UnreachableCode(count) => write!(f, "{} unreachable instruction(s)", count),
+            WarningKind::UnusedLabel(name) => write!(f, "label `{}` is never used", name),
+            WarningKind::MemoryJump(instruction) => {
+                write!(f, "`{}` accesses M and jumps, both use the address in A", instruction)
+            }
+            WarningKind::DiscardedComputation(comp) => write!(f, "jump tests `{}` but the result is not stored", comp),
+            WarningKind::CaseMismatch { variable, symbol } => {
+                write!(f, "variable `{}` differs from `{}` only by case", variable, symbol)
+            }
+            WarningKind::SingleUse(name) => write!(f, "variable `{}` is used only once", name),
+            WarningKind::RamOverflow(overflow) => write!(f, "{}", overflow),
+        }
+    }
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(f, "{}:{}: warning: {}", self.line, self.column(), self.kind)?;
+        write_expansion(f, &self.expanded_from)
+    }
+}
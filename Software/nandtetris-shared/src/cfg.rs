@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use crate::assembler::{Address, CodeLine, Jump, Term};
+
+/// A run of lines that is only entered at its start and only left at its end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// Indices into the code the graph was built from.
+    pub lines: Range<usize>,
+    /// Blocks control can pass to, by jump or by falling through.
+    pub successors: Vec<usize>,
+    /// Ends with a jump whose target is not a known label or address, e.g. a `return`.
+    pub indirect_jump: bool,
+    /// Labels loaded into A by the block, which may be jumped to indirectly.
+    pub loaded_labels: Vec<String>,
+}
+
+/// Control flow of a program, blocks start at labels and end after jumping C-instructions.
+///
+/// Indirect jumps are assumed to target labels whose address was loaded by reachable code,
+/// as the VM translator does for return addresses, or the code after them up to the next label.
+#[derive(Debug, Clone, Default)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+    labels: HashMap<String, usize>,
+}
+
+impl ControlFlowGraph {
+    pub fn new(code: &[CodeLine]) -> Self {
+        let mut starts = Vec::new();
+        for (idx, line) in code.iter().enumerate() {
+            let after_jump = idx > 0 && matches!(code[idx - 1], CodeLine::C { jump, .. } if jump != Jump::Null);
+            if idx == 0 || after_jump || matches!(line, CodeLine::Label(_)) {
+                starts.push(idx);
+            }
+        }
+        starts.dedup();
+        let ranges = starts.iter()
+            .zip(starts.iter().skip(1).chain(std::iter::once(&code.len())))
+            .map(|(&start, &end)| start..end)
+            .collect::<Vec<_>>();
+
+        let labels = ranges.iter()
+            .enumerate()
+            .filter_map(|(block, range)| match &code[range.start] {
+                CodeLine::Label(name) => Some((name.clone(), block)),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+
+        // ROM address of the first instruction of every block, for numeric jump targets
+        let mut addresses = Vec::with_capacity(ranges.len());
+        let mut address = 0;
+        for range in &ranges {
+            addresses.push(address);
+            address += code[range.clone()].iter().filter(|x| !matches!(x, CodeLine::Label(_))).count();
+        }
+        let block_at = |target: usize| addresses.iter().rposition(|&x| x <= target).filter(|_| target < address);
+
+        let mut a = None;
+        let mut blocks = Vec::with_capacity(ranges.len());
+        for (block, range) in ranges.iter().enumerate() {
+            let mut successors = Vec::new();
+            let mut indirect_jump = false;
+            let mut loaded_labels = Vec::new();
+            let mut falls_through = true;
+            for line in &code[range.clone()] {
+                match line {
+                    CodeLine::Label(_) => a = None,
+                    CodeLine::A(address) => {
+                        match address {
+                            Address::Variable(name) if labels.contains_key(name.as_ref()) => {
+                                loaded_labels.push(name.to_string());
+                            }
+                            Address::Expression(expression) => {
+                                loaded_labels.extend(expression.terms.iter().filter_map(|(_, term)| match term {
+                                    Term::Symbol(name) if labels.contains_key(name) => Some(name.clone()),
+                                    _ => None,
+                                }));
+                            }
+                            _ => {}
+                        }
+                        a = Some(address);
+                    }
+                    CodeLine::C { dest, jump, .. } => {
+                        if *jump != Jump::Null {
+                            let target = match a {
+                                Some(Address::Variable(name)) => labels.get(name.as_ref()).copied(),
+                                Some(Address::Value(value)) => block_at(usize::from(*value)),
+                                _ => None,
+                            };
+                            match target {
+                                Some(target) => successors.push(target),
+                                None => indirect_jump = true,
+                            }
+                            falls_through = *jump != Jump::JMP;
+                        }
+                        if dest.a {
+                            a = None;
+                        }
+                    }
+                }
+            }
+            if falls_through && block + 1 < ranges.len() {
+                successors.push(block + 1);
+            }
+            successors.dedup();
+            blocks.push(BasicBlock { lines: range.clone(), successors, indirect_jump, loaded_labels });
+        }
+        ControlFlowGraph { blocks, labels }
+    }
+
+    /// The block a label starts.
+    pub fn label_block(&self, name: &str) -> Option<usize> {
+        self.labels.get(name).copied()
+    }
+
+    /// Which blocks can run when the program starts at its first line.
+    /// `external` names labels whose address is taken outside the code, e.g. by data directives.
+    ///
+    /// A taken label may be offset before it is jumped to, as in jump tables, so every block
+    /// up to the next label counts as a target of indirect jumps.
+    pub fn reachable(&self, external: &[&str]) -> Vec<bool> {
+        let label_blocks = self.labels.values().copied().collect::<HashSet<_>>();
+        let region = |block: usize| {
+            let end = (block + 1..self.blocks.len()).find(|x| label_blocks.contains(x)).unwrap_or(self.blocks.len());
+            block..end
+        };
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut taken = external.iter().filter_map(|x| self.label_block(x)).collect::<Vec<_>>();
+        let mut indirect = false;
+        let mut stack = if self.blocks.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(block) = stack.pop() {
+            if std::mem::replace(&mut reachable[block], true) {
+                continue;
+            }
+            let block = &self.blocks[block];
+            stack.extend(&block.successors);
+            taken.extend(block.loaded_labels.iter().filter_map(|x| self.label_block(x)));
+            indirect |= block.indirect_jump;
+            if indirect {
+                stack.extend(taken.drain(..).flat_map(region));
+            }
+        }
+        reachable
+    }
+}
+
+/// Lines whose ROM address other code depends on, so passes must neither drop nor rewrite them.
+///
+/// These are the lines from a label used in arithmetic, e.g. `@TABLE+2`, up to the next label,
+/// and all lines up to the next label after the highest numeric jump target, e.g. `@12 / 0;JMP`.
+/// `offset_labels` names labels used in arithmetic elsewhere, e.g. by `.equ`.
+pub fn fixed_layout(code: &[CodeLine], offset_labels: &[&str]) -> Vec<bool> {
+    let offset_labels = code.iter()
+        .filter_map(|line| match line {
+            CodeLine::A(Address::Expression(expression)) => Some(expression),
+            _ => None,
+        })
+        .flat_map(|expression| &expression.terms)
+        .filter_map(|(_, term)| match term {
+            Term::Symbol(name) => Some(name.as_str()),
+            Term::Number(_) => None,
+        })
+        .chain(offset_labels.iter().copied())
+        .collect::<HashSet<_>>();
+
+    let mut a = None;
+    let mut numeric_target = None;
+    for line in code {
+        match line {
+            CodeLine::Label(_) => a = None,
+            CodeLine::A(Address::Value(value)) => a = Some(usize::from(*value)),
+            CodeLine::A(_) => a = None,
+            CodeLine::C { dest, jump, .. } => {
+                if *jump != Jump::Null {
+                    numeric_target = numeric_target.max(a);
+                }
+                if dest.a {
+                    a = None;
+                }
+            }
+        }
+    }
+
+    let mut fixed = Vec::with_capacity(code.len());
+    let mut address = 0;
+    let mut before_target = numeric_target.is_some();
+    let mut offset_region = false;
+    for line in code {
+        if let CodeLine::Label(name) = line {
+            before_target &= numeric_target.is_some_and(|target| address <= target);
+            offset_region = offset_labels.contains(name.as_str());
+        } else {
+            address += 1;
+        }
+        fixed.push(before_target || offset_region);
+    }
+    fixed
+}
+
+/// Indices of labels no A-instruction refers to. `external` names labels used elsewhere.
+pub fn unused_labels(code: &[CodeLine], external: &[&str]) -> Vec<usize> {
+    let used = code.iter()
+        .flat_map(|line| match line {
+            CodeLine::A(Address::Variable(name)) => vec![name.as_ref()],
+            CodeLine::A(Address::Expression(expression)) => expression.terms.iter()
+                .filter_map(|(_, term)| match term {
+                    Term::Symbol(name) => Some(name.as_str()),
+                    Term::Number(_) => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        })
+        .chain(external.iter().copied())
+        .collect::<HashSet<_>>();
+    code.iter()
+        .enumerate()
+        .filter(|(_, line)| matches!(line, CodeLine::Label(name) if !used.contains(name.as_str())))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(code: &str) -> Vec<CodeLine> {
+        code.lines().enumerate().filter_map(|(idx, line)| CodeLine::parse(idx + 1, line).unwrap()).collect()
+    }
+
+    #[test]
+    fn splits_blocks() {
+        let code = parse("@R0\nD=M\n@END\nD;JEQ\nD=D-1\n(END)\n@END\n0;JMP\n@R1\n");
+        let graph = ControlFlowGraph::new(&code);
+        let blocks = graph.blocks.iter().map(|x| (x.lines.clone(), x.successors.clone())).collect::<Vec<_>>();
+        assert_eq!(blocks, [(0..4, vec![2, 1]), (4..5, vec![2]), (5..8, vec![2]), (8..9, vec![])]);
+        assert_eq!(graph.reachable(&[]), [true, true, true, false]);
+        assert_eq!(unused_labels(&code, &[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn follows_indirect_jumps() {
+        let code = parse("\
+@RET
+D=A
+@FUNC
+0;JMP
+(RET)
+@RET
+0;JMP
+(FUNC)
+@R13
+A=M
+0;JMP
+(UNUSED)
+D=0
+(TABLE)
+D=1
+");
+        let graph = ControlFlowGraph::new(&code);
+        assert!(graph.blocks[2].indirect_jump);
+        assert_eq!(graph.reachable(&[]), [true, true, true, false, false]);
+        assert_eq!(graph.reachable(&["TABLE"]), [true, true, true, false, true]);
+        assert_eq!(unused_labels(&code, &["TABLE"]), [11]);
+    }
+
+    #[test]
+    fn fixes_layout_relative_code() {
+        let code = parse("@TABLE+1\n0;JMP\n(TABLE)\n@0\n@1\n(NEXT)\n@2\n");
+        assert_eq!(fixed_layout(&code, &[]), [false, false, true, true, true, false, false]);
+        assert_eq!(fixed_layout(&code, &["NEXT"]), [false, false, true, true, true, true, true]);
+
+        let code = parse("@3\n0;JMP\n(A)\n@0\n@1\n(B)\n@2\n");
+        assert_eq!(fixed_layout(&code, &[]), [true, true, true, true, true, false, false]);
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeSet;
+use crate::assembler::{Address, CodeLine, DecodeError, Jump};
+
+/// A `.hack` line that is not a 16 digit binary word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HackParseError {
+    pub line: usize,
+    pub text: String,
+}
+
+impl std::fmt::Display for HackParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: expected 16 binary digits, found `{}`", self.line, self.text)
+    }
+}
+
+impl std::error::Error for HackParseError {}
+
+/// Reads the ASCII `.hack` format, one `{:016b}` word per line. Blank lines are skipped.
+pub fn parse_hack(content: &str) -> Result<Vec<u16>, Vec<HackParseError>> {
+    let mut words = Vec::new();
+    let mut errors = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match u16::from_str_radix(line, 2) {
+            Ok(word) if line.len() == 16 => words.push(word),
+            _ => errors.push(HackParseError { line: idx + 1, text: line.to_string() }),
+        }
+    }
+    if errors.is_empty() {
+        Ok(words)
+    } else {
+        Err(errors)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Disassembled {
+    Code(CodeLine),
+    /// A word that decodes to nothing, rendered as a comment so the output still assembles.
+    Invalid { address: u16, error: DecodeError },
+}
+
+impl std::fmt::Display for Disassembled {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Disassembled::Code(line) => write!(f, "{}", line),
+            Disassembled::Invalid { address, error } => write!(f, "// ROM[{}] {}", address, error),
+        }
+    }
+}
+
+pub fn disassemble(words: &[u16]) -> Vec<Disassembled> {
+    words.iter().enumerate().map(|(address, &word)| {
+        match CodeLine::try_from(word) {
+            Ok(line) => Disassembled::Code(line),
+            Err(error) => Disassembled::Invalid { address: address as u16, error },
+        }
+    }).collect()
+}
+
+/// Name given to a recovered jump target.
+pub fn label_name(address: u16) -> String {
+    format!("L_{}", address)
+}
+
+/// Replaces A-values that feed a jumping C-instruction with synthetic `(L_n)` labels.
+///
+/// `lines` must be the one-line-per-word output of [`disassemble`]. Every A-instruction that is
+/// still in the A register when a jump executes gets rewritten to `@L_n`, and a `(L_n)` label is
+/// inserted before ROM address `n`. Other loads of the same value stay numeric, since they may be
+/// data. Targets past the end of the program are left alone.
+pub fn recover_labels(lines: Vec<Disassembled>) -> Vec<Disassembled> {
+    let mut feeds_jump = vec![false; lines.len()];
+    let mut loaded = None;
+    for (idx, line) in lines.iter().enumerate() {
+        match line {
+            Disassembled::Code(CodeLine::A(Address::Value(_))) => loaded = Some(idx),
+            Disassembled::Code(CodeLine::C { dest, jump, .. }) => {
+                if let (Some(load_idx), true) = (loaded, *jump != Jump::Null) {
+                    feeds_jump[load_idx] = true;
+                }
+                if dest.a {
+                    loaded = None;
+                }
+            }
+            _ => loaded = None,
+        }
+    }
+
+    let len = lines.len();
+    let mut targets = BTreeSet::new();
+    let mut lines = lines;
+    for (line, _) in lines.iter_mut().zip(feeds_jump).filter(|(_, feeds)| *feeds) {
+        if let Disassembled::Code(CodeLine::A(Address::Value(value))) = line {
+            if usize::from(*value) <= len {
+                targets.insert(*value);
+                *line = Disassembled::Code(CodeLine::variable(label_name(*value)));
+            }
+        }
+    }
+
+    let mut result = Vec::with_capacity(len + targets.len());
+    for (address, line) in lines.into_iter().enumerate() {
+        if targets.contains(&(address as u16)) {
+            result.push(Disassembled::Code(CodeLine::Label(label_name(address as u16))));
+        }
+        result.push(line);
+    }
+    if targets.contains(&(len as u16)) {
+        result.push(Disassembled::Code(CodeLine::Label(label_name(len as u16))));
+    }
+    result
+}
@@ -1 +1,5 @@
 pub mod assembler;
+pub mod cfg;
+pub mod disassembler;
+pub mod object;
+pub mod optimizer;
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use crate::assembler::{Instruction, SymbolKind, SymbolTable, MAX_A_VALUE, ROM_SIZE};
+
+/// How the linker fixes up an A-instruction of a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelocationKind {
+    /// The word holds an address inside the module, the module's ROM base is added.
+    Local,
+    /// The address of a label exported by another module, or of a global variable,
+    /// is added to the word.
+    Import(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relocation {
+    /// Index of the A-instruction in the module's code.
+    pub offset: u16,
+    pub kind: RelocationKind,
+}
+
+/// A separately assembled module, placed in ROM and given variables by [`link`].
+///
+/// Every label is exported. Symbols that are neither defined in the module nor predefined
+/// are imported: they resolve to another module's label, or become a variable shared by
+/// all modules.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Object {
+    pub code: Vec<u16>,
+    /// Labels with their address inside the module.
+    pub exports: Vec<(String, u16)>,
+    pub imports: Vec<String>,
+    pub relocations: Vec<Relocation>,
+}
+
+impl Object {
+    /// Writes the `.hobj` text format: `export NAME ADDRESS` and `import NAME` lines,
+    /// then one binary word per line, followed by `local` or `import NAME` if it is relocated.
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "// hack object")?;
+        for (name, address) in &self.exports {
+            writeln!(writer, "export {} {}", name, address)?;
+        }
+        for name in &self.imports {
+            writeln!(writer, "import {}", name)?;
+        }
+        let relocations = self.relocations.iter().map(|x| (x.offset, &x.kind)).collect::<HashMap<_, _>>();
+        for (offset, word) in self.code.iter().enumerate() {
+            match relocations.get(&(offset as u16)) {
+                None => writeln!(writer, "{:016b}", word)?,
+                Some(RelocationKind::Local) => writeln!(writer, "{:016b} local", word)?,
+                Some(RelocationKind::Import(name)) => writeln!(writer, "{:016b} import {}", word, name)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the format written by [`Object::write`].
+    pub fn read(content: &str) -> Result<Self, String> {
+        let mut object = Object::default();
+        for (idx, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            let error = |message: &str| format!("line {}: {}", idx + 1, message);
+            let parts = line.split_whitespace().collect::<Vec<_>>();
+            match parts[..] {
+                ["export", name, address] => {
+                    let address = address.parse().map_err(|_| error("invalid address"))?;
+                    object.exports.push((name.to_string(), address));
+                }
+                ["import", name] => object.imports.push(name.to_string()),
+                [word, ref relocation @ ..] => {
+                    let offset = object.code.len() as u16;
+                    let word = u16::from_str_radix(word, 2)
+                        .ok()
+                        .filter(|_| word.len() == 16)
+                        .ok_or_else(|| error("expected 16 binary digits"))?;
+                    object.code.push(word);
+                    let kind = match relocation {
+                        [] => continue,
+                        ["local"] => RelocationKind::Local,
+                        ["import", name] if object.imports.iter().any(|x| x == name) => {
+                            RelocationKind::Import(name.to_string())
+                        }
+                        ["import", _] => return Err(error("relocation refers to a symbol that is not imported")),
+                        _ => return Err(error("expected `local` or `import NAME` after the word")),
+                    };
+                    object.relocations.push(Relocation { offset, kind });
+                }
+                [] => {}
+            }
+        }
+        Ok(object)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkError {
+    DuplicateLabel { name: String, first: String, second: String },
+    /// A relocated A-instruction ended up with a value that does not fit in 15 bits.
+    AddressOutOfRange { module: String, offset: u16, value: u32 },
+    RomOverflow(usize),
+    /// No RAM cell is left for an imported symbol that no module exports.
+    RamOverflow(String),
+}
+
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LinkError::DuplicateLabel { name, first, second } => {
+                write!(f, "label `{}` is exported by both {} and {}", name, first, second)
+            }
+            LinkError::AddressOutOfRange { module, offset, value } => {
+                write!(f, "{}: relocated A-value {} at offset {} does not fit in 15 bits", module, value, offset)
+            }
+            LinkError::RomOverflow(size) => {
+                write!(f, "linked program needs {} instructions, the ROM only holds {}", size, ROM_SIZE)
+            }
+            LinkError::RamOverflow(name) => write!(f, "no RAM is left for variable `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+/// Places the modules in ROM in the given order and resolves their relocations.
+/// The first module starts at address 0. Variables are allocated in the order they are first
+/// referenced. Returns the program with the global symbol table.
+pub fn link<'a>(modules: &[(&'a str, &'a Object)]) -> Result<(Vec<Instruction>, SymbolTable), Vec<LinkError>> {
+    let mut errors = Vec::new();
+    let mut symbol_table = SymbolTable::default();
+    let mut exported_by = HashMap::new();
+    let mut bases = Vec::with_capacity(modules.len());
+    let mut size = 0;
+    for &(module, object) in modules {
+        let base = size;
+        bases.push(base);
+        size += object.code.len();
+        for (name, address) in &object.exports {
+            let address = u16::try_from(base + usize::from(*address)).unwrap_or(u16::MAX);
+            if symbol_table.insert(name.clone().into(), address, SymbolKind::Label).is_ok() {
+                exported_by.insert(name.as_str(), module);
+            } else {
+                let first = exported_by.get(name.as_str()).copied().unwrap_or("the platform").to_string();
+                errors.push(LinkError::DuplicateLabel { name: name.clone(), first, second: module.to_string() });
+            }
+        }
+    }
+    if size > ROM_SIZE {
+        errors.push(LinkError::RomOverflow(size));
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut program = Vec::with_capacity(size);
+    for (&(module, object), base) in modules.iter().zip(bases) {
+        let mut code = object.code.clone();
+        for relocation in &object.relocations {
+            let word = &mut code[usize::from(relocation.offset)];
+            let target = match &relocation.kind {
+                RelocationKind::Local => base as u32,
+                RelocationKind::Import(name) => match symbol_table.get_or_insert(name.clone().into()) {
+                    Ok(address) => u32::from(address),
+                    Err(_) => {
+                        errors.push(LinkError::RamOverflow(name.clone()));
+                        continue;
+                    }
+                },
+            };
+            let value = u32::from(*word) + target;
+            match u16::try_from(value) {
+                Ok(value) if value <= MAX_A_VALUE => *word = value,
+                _ => errors.push(LinkError::AddressOutOfRange { module: module.to_string(), offset: relocation.offset, value }),
+            }
+        }
+        program.extend(code.into_iter().map(Instruction));
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok((program, symbol_table))
+}
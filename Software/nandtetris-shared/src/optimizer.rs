@@ -0,0 +1,222 @@
+use std::collections::HashSet;
+use crate::assembler::{Address, CodeLine, Comp, Dest, Jump};
+
+/// Removes instructions that have no effect, e.g. `@SP / M=M+1 / @SP / M=M-1`.
+///
+/// Labels are kept and no rewrite looks across one, so every jump target still sees
+/// the same machine state. The passes run until none of them shortens the code.
+pub fn optimize(code: Vec<CodeLine>) -> Vec<CodeLine> {
+    let lines = code.into_iter().map(|x| ((), x)).collect();
+    optimize_with(lines).into_iter().map(|(_, x)| x).collect()
+}
+
+/// Like [`optimize`], carrying data such as source positions along with each line.
+/// A line that replaces two others keeps the data of the first one.
+pub fn optimize_with<T>(mut lines: Vec<(T, CodeLine)>) -> Vec<(T, CodeLine)> {
+    loop {
+        let before = lines.len();
+        lines = redundant_loads(lines);
+        lines = inverse_pairs(lines);
+        lines = fuse_assignments(lines);
+        lines = dead_d_writes(lines);
+        lines = constant_d(lines);
+        lines = dead_loads(lines);
+        if lines.len() == before {
+            return lines;
+        }
+    }
+}
+
+/// Whether `line` is a C-instruction without jump that only writes `dest`.
+fn is_assign(line: &CodeLine, to: Dest) -> Option<Comp> {
+    match line {
+        CodeLine::C { comp, dest, jump: Jump::Null } if *dest == to => Some(*comp),
+        _ => None,
+    }
+}
+
+/// `@X` when A already holds `X`.
+fn redundant_loads<T>(lines: Vec<(T, CodeLine)>) -> Vec<(T, CodeLine)> {
+    let mut known = None;
+    lines.into_iter()
+        .filter(|(_, line)| match line {
+            CodeLine::Label(_) => {
+                known = None;
+                true
+            }
+            CodeLine::A(address) if known.as_ref() == Some(address) => false,
+            CodeLine::A(address) => {
+                known = Some(address.clone());
+                true
+            }
+            CodeLine::C { dest, .. } => {
+                if dest.a {
+                    known = None;
+                }
+                true
+            }
+        })
+        .collect()
+}
+
+/// `r=r+1` directly followed by `r=r-1` or the other way round.
+fn inverse_pairs<T>(lines: Vec<(T, CodeLine)>) -> Vec<(T, CodeLine)> {
+    let pairs = [
+        (Dest::A, Comp::APlusOne, Comp::AMinusOne),
+        (Dest::D, Comp::DPlusOne, Comp::DMinusOne),
+        (Dest::M, Comp::MPlusOne, Comp::MMinusOne),
+    ];
+    let mut result: Vec<(T, CodeLine)> = Vec::with_capacity(lines.len());
+    for (data, line) in lines {
+        let cancels = result.last().is_some_and(|(_, previous)| {
+            pairs.iter().any(|&(dest, inc, dec)| {
+                matches!(
+                    (is_assign(previous, dest), is_assign(&line, dest)),
+                    (Some(a), Some(b)) if (a, b) == (inc, dec) || (a, b) == (dec, inc)
+                )
+            })
+        });
+        if cancels {
+            result.pop();
+        } else {
+            result.push((data, line));
+        }
+    }
+    result
+}
+
+/// `M=x` followed by `A=M` or `D=M` becomes `AM=x` or `MD=x`.
+fn fuse_assignments<T>(lines: Vec<(T, CodeLine)>) -> Vec<(T, CodeLine)> {
+    let mut result: Vec<(T, CodeLine)> = Vec::with_capacity(lines.len());
+    for (data, line) in lines {
+        if let Some((_, previous)) = result.last_mut() {
+            if let Some(comp) = is_assign(previous, Dest::M) {
+                let fused = if is_assign(&line, Dest::A) == Some(Comp::M) {
+                    Some(Dest { a: true, m: true, d: false })
+                } else if is_assign(&line, Dest::D) == Some(Comp::M) {
+                    Some(Dest { a: false, m: true, d: true })
+                } else {
+                    None
+                };
+                if let Some(dest) = fused {
+                    *previous = CodeLine::assign(dest, comp);
+                    continue;
+                }
+            }
+        }
+        result.push((data, line));
+    }
+    result
+}
+
+/// `D=x` when D is written again before it is read, jumped on or a label is reached.
+fn dead_d_writes<T>(lines: Vec<(T, CodeLine)>) -> Vec<(T, CodeLine)> {
+    let dead = (0..lines.len())
+        .map(|idx| {
+            if is_assign(&lines[idx].1, Dest::D).is_none() {
+                return false;
+            }
+            for (_, line) in &lines[idx + 1..] {
+                match line {
+                    CodeLine::Label(_) => return false,
+                    CodeLine::A(_) => {}
+                    CodeLine::C { comp, jump, .. } if comp.reads_d() || *jump != Jump::Null => return false,
+                    CodeLine::C { dest, .. } if dest.d => return true,
+                    CodeLine::C { .. } => {}
+                }
+            }
+            false
+        })
+        .collect::<Vec<_>>();
+    lines.into_iter().zip(dead).filter(|(_, dead)| !dead).map(|(line, _)| line).collect()
+}
+
+/// `@0 / D=A` or `@1 / D=A` becomes `D=0` or `D=1` when A is loaded again right after.
+fn constant_d<T>(lines: Vec<(T, CodeLine)>) -> Vec<(T, CodeLine)> {
+    let mut result: Vec<(T, CodeLine)> = Vec::with_capacity(lines.len());
+    let mut lines = lines.into_iter().peekable();
+    while let Some((data, line)) = lines.next() {
+        let comp = match &line {
+            CodeLine::A(Address::Value(0)) => Comp::Zero,
+            CodeLine::A(Address::Value(1)) => Comp::One,
+            _ => {
+                result.push((data, line));
+                continue;
+            }
+        };
+        let loads_d = lines.peek().is_some_and(|(_, next)| is_assign(next, Dest::D) == Some(Comp::A));
+        if loads_d {
+            let (next_data, next) = lines.next().expect("Peeked line");
+            if matches!(lines.peek(), Some((_, CodeLine::A(_)))) {
+                result.push((data, CodeLine::assign(Dest::D, comp)));
+            } else {
+                result.push((data, line));
+                result.push((next_data, next));
+            }
+        } else {
+            result.push((data, line));
+        }
+    }
+    result
+}
+
+/// `@X` directly followed by another A-instruction. The first use of a name is kept,
+/// as it may be a variable and dropping it would move the others in RAM.
+fn dead_loads<T>(lines: Vec<(T, CodeLine)>) -> Vec<(T, CodeLine)> {
+    let mut seen = HashSet::new();
+    let mut result: Vec<(T, CodeLine)> = Vec::with_capacity(lines.len());
+    for (data, line) in lines {
+        if let CodeLine::A(_) = line {
+            if let Some((_, CodeLine::A(previous))) = result.last() {
+                let first_use = matches!(previous, Address::Variable(name) if !seen.contains(name));
+                if !first_use {
+                    result.pop();
+                }
+            }
+        }
+        if let Some((_, CodeLine::A(Address::Variable(name)))) = result.last() {
+            seen.insert(name.clone());
+        }
+        result.push((data, line));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(code: &str) -> Vec<CodeLine> {
+        code.lines().enumerate().filter_map(|(idx, line)| CodeLine::parse(idx + 1, line).unwrap()).collect()
+    }
+
+    fn optimized(code: &str) -> Vec<String> {
+        optimize(parse(code)).iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn removes_stack_round_trip() {
+        let code = "@SP\nA=M\nM=D\n@SP\nM=M+1\n@SP\nM=M-1\n@SP\nA=M\nD=M\n";
+        assert_eq!(optimized(code), ["@SP", "A=M", "M=D", "@SP", "A=M", "D=M"]);
+        let code = "@SP\nM=M-1\n@SP\nA=M\nD=M\n";
+        assert_eq!(optimized(code), ["@SP", "AM=M-1", "D=M"]);
+    }
+
+    #[test]
+    fn folds_loads() {
+        assert_eq!(optimized("@5\nD=A\n@7\nD=A\n@R0\nM=D\n"), ["@7", "D=A", "@R0", "M=D"]);
+        assert_eq!(optimized("@0\nD=A\n@R0\nM=D\n"), ["D=0", "@R0", "M=D"]);
+        assert_eq!(optimized("@x\n@y\n@x\n@z\nM=0\n"), ["@x", "@y", "@z", "M=0"]);
+        assert_eq!(optimized("@R1\nD=A\n@R1\nM=D\n"), ["@R1", "D=A", "M=D"]);
+    }
+
+    #[test]
+    fn keeps_label_targets() {
+        let code = "@SP\nM=M+1\n(LOOP)\n@SP\nM=M-1\n@LOOP\nD;JGT\n";
+        assert_eq!(optimized(code), ["@SP", "M=M+1", "(LOOP)", "@SP", "M=M-1", "@LOOP", "D;JGT"]);
+        let code = "D=1\n(LOOP)\nD=0\n";
+        assert_eq!(optimized(code), ["D=1", "(LOOP)", "D=0"]);
+        let code = "D=1\n@END\nD;JEQ\nD=0\n";
+        assert_eq!(optimized(code), ["D=1", "@END", "D;JEQ", "D=0"]);
+    }
+}
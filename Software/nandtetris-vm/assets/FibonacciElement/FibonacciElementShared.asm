@@ -0,0 +1,325 @@
+@256
+D=A
+@SP
+M=D
+@Sys.init
+D=A
+@R13
+M=D
+@0
+D=A
+@R14
+M=D
+@Bootstrap$ret$1
+D=A
+@R15
+M=D
+@$$call
+0;JMP
+(Bootstrap$ret$1)
+(Main.fibonacci)
+@0
+D=A
+@ARG
+A=M
+AD=D+A
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@2
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+M=M-1
+@SP
+A=M
+D=M
+@SP
+M=M-1
+@SP
+A=M
+A=M
+D=A-D
+@LABEL2
+D;JLT
+@SP
+A=M
+M=0
+@LABEL3
+0;JMP
+(LABEL2)
+@SP
+A=M
+M=-1
+(LABEL3)
+@SP
+M=M+1
+@SP
+M=M-1
+@SP
+A=M
+D=M
+@Main.fibonacci$IF_TRUE
+D;JNE
+@Main.fibonacci$IF_FALSE
+0;JMP
+(Main.fibonacci$IF_TRUE)
+@0
+D=A
+@ARG
+A=M
+AD=D+A
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@$$return
+0;JMP
+(Main.fibonacci$IF_FALSE)
+@0
+D=A
+@ARG
+A=M
+AD=D+A
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@2
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+M=M-1
+@SP
+A=M
+D=M
+@SP
+M=M-1
+@SP
+A=M
+A=M
+D=A-D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@Main.fibonacci
+D=A
+@R13
+M=D
+@1
+D=A
+@R14
+M=D
+@Main.fibonacci$ret$4
+D=A
+@R15
+M=D
+@$$call
+0;JMP
+(Main.fibonacci$ret$4)
+@0
+D=A
+@ARG
+A=M
+AD=D+A
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+M=M-1
+@SP
+A=M
+D=M
+@SP
+M=M-1
+@SP
+A=M
+A=M
+D=A-D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@Main.fibonacci
+D=A
+@R13
+M=D
+@1
+D=A
+@R14
+M=D
+@Main.fibonacci$ret$5
+D=A
+@R15
+M=D
+@$$call
+0;JMP
+(Main.fibonacci$ret$5)
+@SP
+M=M-1
+@SP
+A=M
+D=M
+@SP
+M=M-1
+@SP
+A=M
+A=M
+D=D+A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@$$return
+0;JMP
+(Sys.init)
+@4
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@Main.fibonacci
+D=A
+@R13
+M=D
+@1
+D=A
+@R14
+M=D
+@Sys.init$ret$6
+D=A
+@R15
+M=D
+@$$call
+0;JMP
+(Sys.init$ret$6)
+(Sys.init$WHILE)
+@Sys.init$WHILE
+0;JMP
+($$call)
+@R15
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@LCL
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@ARG
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THIS
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THAT
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@R14
+D=M
+@5
+D=D+A
+@SP
+D=M-D
+@ARG
+M=D
+@SP
+D=M
+@LCL
+M=D
+@R13
+A=M
+0;JMP
+($$return)
+@LCL
+D=M
+@R13
+M=D
+@5
+A=D-A
+D=M
+@R14
+M=D
+@SP
+M=M-1
+@SP
+A=M
+D=M
+@ARG
+A=M
+M=D
+@ARG
+D=M+1
+@SP
+M=D
+@R13
+AM=M-1
+D=M
+@THAT
+M=D
+@R13
+AM=M-1
+D=M
+@THIS
+M=D
+@R13
+AM=M-1
+D=M
+@ARG
+M=D
+@R13
+AM=M-1
+D=M
+@LCL
+M=D
+@R14
+A=M
+0;JMP
@@ -1,59 +1,240 @@
+use std::collections::HashSet;
 use std::str::FromStr;
 use nandtetris_shared::assembler::{self, CodeLine};
+use nandtetris_shared::optimizer;
+use crate::error::{Error, ErrorKind};
+
+/// First RAM address of the `temp` segment.
+const TEMP_BASE: u16 = 5;
+/// Labels of the routines every call and return jumps to with shared calls, see [`Context::translate_files`].
+const CALL_ROUTINE: &str = "$$call";
+const RETURN_ROUTINE: &str = "$$return";
 
 #[derive(Debug)]
 pub struct Context {
     label_index: u16,
+    /// Runs the peephole optimizer over the generated assembly.
+    pub optimize: bool,
+    /// Name of the translated file without `.vm`, static variables are named `<file_name>.<index>`.
+    pub file_name: String,
+    /// Calls and returns jump to the shared routines, only set while [`Context::translate_files`] runs
+    /// because it emits the routines.
+    shared_calls: bool,
+    /// The function being translated, labels are local to it.
+    function: Option<String>,
 }
 
 impl Default for Context {
     fn default() -> Self {
-        Self { label_index: 1 }
+        Self { label_index: 1, optimize: false, file_name: String::new(), shared_calls: false, function: None }
     }
 }
 
 impl Context {
-    pub fn translate(&mut self, code: &str) -> Vec<String> {
-        let instructions = self.parse(code);
-        let assembler = instructions.into_iter().flat_map(|x| self.translate_instruction(x));
-        let output = assembler.map(|x| x.to_string()).collect::<Vec<_>>();
-        output
+    pub fn translate(&mut self, code: &str) -> Result<Vec<String>, Vec<Error>> {
+        Ok(self.translate_code(code)?.into_iter().map(|x| x.to_string()).collect())
+    }
+
+    /// Translates to assembly without rendering it, so it can go straight to
+    /// [`assembler::Context::assemble_lines`].
+    pub fn translate_code(&mut self, code: &str) -> Result<Vec<CodeLine>, Vec<Error>> {
+        let instructions = self.parse(code)?;
+        self.translate_parsed(instructions)
     }
 
-    fn parse(&self, code: &str) -> Vec<VmInstruction> {
-        let code_lines = code.lines()
-            .map(|line| {
-                if let Some(comment_idx) = line.find("//") {
-                    line[..comment_idx].trim()
-                } else {
-                    line.trim()
+    fn translate_parsed(&mut self, instructions: Vec<(usize, VmInstruction)>) -> Result<Vec<CodeLine>, Vec<Error>> {
+        self.function = None;
+        let mut errors = Vec::new();
+        let mut labels = HashSet::new();
+        let mut jumps = Vec::new();
+        let mut code = Vec::new();
+        for (line, instruction) in instructions {
+            match &instruction {
+                VmInstruction::Label(label) if !labels.insert(self.scoped_label(label)) => {
+                    errors.push(Error { line, kind: ErrorKind::DuplicateLabel(label.clone()) });
+                }
+                VmInstruction::Goto(label) | VmInstruction::IfGoto(label) => {
+                    jumps.push((line, label.clone(), self.scoped_label(label)));
                 }
+                _ => {}
+            }
+            code.extend(self.translate_instruction(instruction));
+        }
+        for (line, label, scoped) in jumps {
+            if !labels.contains(&scoped) {
+                errors.push(Error { line, kind: ErrorKind::UndefinedLabel(label) });
+            }
+        }
+        if !errors.is_empty() {
+            errors.sort_by_key(|x| x.line);
+            return Err(errors);
+        }
+        Ok(if self.optimize {
+            optimizer::optimize(code)
+        } else {
+            code
+        })
+    }
+
+    /// Translates the files of a program, given as `(name, code)` with the name of each file without `.vm`,
+    /// into one piece of assembly. It starts with the bootstrap code if `bootstrap` is set and one of the
+    /// files is `Sys`. Every called function has to be defined in one of the files.
+    /// The errors are returned along with the name of their file, ordered like the files.
+    ///
+    /// With `shared_calls`, calls and returns jump to one `$$call` and one `$$return` routine at the end
+    /// instead of inlining the frame handling. A call site passes the target, the argument count and the
+    /// return address in R13 to R15.
+    pub fn translate_files(
+        &mut self,
+        files: &[(&str, &str)],
+        bootstrap: bool,
+        shared_calls: bool,
+    ) -> Result<Vec<CodeLine>, Vec<(String, Error)>> {
+        let mut errors = Vec::new();
+        let mut parsed = Vec::new();
+        for (name, file) in files {
+            match self.parse(file) {
+                Ok(instructions) => parsed.push((*name, instructions)),
+                Err(file_errors) => errors.extend(file_errors.into_iter().map(|x| (name.to_string(), x))),
+            }
+        }
+        let functions = parsed.iter()
+            .flat_map(|(_, instructions)| instructions)
+            .filter_map(|(_, instruction)| match instruction {
+                VmInstruction::Function { name, .. } => Some(name.as_str()),
+                _ => None,
             })
-            .filter(|x| !x.is_empty())
-            .map(|x| Self::parse_line(x))
+            .collect::<HashSet<_>>();
+        for (name, instructions) in &parsed {
+            for (line, instruction) in instructions {
+                match instruction {
+                    VmInstruction::Call { name: function, .. } if !functions.contains(function.as_str()) => {
+                        let kind = ErrorKind::UndefinedFunction(function.clone());
+                        errors.push((name.to_string(), Error { line: *line, kind }));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let bootstrap = bootstrap && files.iter().any(|(name, _)| *name == "Sys");
+        if bootstrap && !functions.contains("Sys.init") {
+            errors.push(("Sys".to_string(), Error { line: 0, kind: ErrorKind::MissingSysInit }));
+        }
 
-            .collect::<Vec<_>>();
-        code_lines
+        self.shared_calls = shared_calls;
+        let mut code = Vec::new();
+        if bootstrap {
+            code.extend(self.bootstrap());
+        }
+        for (name, instructions) in parsed {
+            self.file_name = name.to_string();
+            match self.translate_parsed(instructions) {
+                Ok(file_code) => code.extend(file_code),
+                Err(file_errors) => errors.extend(file_errors.into_iter().map(|x| (name.to_string(), x))),
+            }
+        }
+        if self.shared_calls {
+            code.extend(call_routine());
+            code.push(CodeLine::Label(RETURN_ROUTINE.to_string()));
+            code.extend(ret());
+            self.shared_calls = false;
+        }
+        if !errors.is_empty() {
+            errors.sort_by_key(|(name, error)| (files.iter().position(|(x, _)| x == name), error.line));
+            return Err(errors);
+        }
+        Ok(code)
     }
 
-    fn parse_line(line: &str) -> VmInstruction {
+    /// Sets SP to 256 and calls `Sys.init`.
+    pub fn bootstrap(&mut self) -> Vec<CodeLine> {
+        use assembler::*;
+
+        // the return label is scoped to the bootstrap code, it is never reached
+        self.function = Some("Bootstrap".to_string());
+        let mut vec = vec![
+            CodeLine::constant(256),
+            CodeLine::assign(Dest::D, Comp::A),
+            predefined_symbols::SP.into(),
+            CodeLine::assign(Dest::M, Comp::D),
+        ];
+        vec.extend(self.call("Sys.init".to_string(), 0));
+        self.function = None;
+        vec
+    }
+
+    fn parse(&self, code: &str) -> Result<Vec<(usize, VmInstruction)>, Vec<Error>> {
+        let mut instructions = Vec::new();
+        let mut errors = Vec::new();
+        for (idx, line) in code.lines().enumerate() {
+            let line = match line.find("//") {
+                Some(comment_idx) => line[..comment_idx].trim(),
+                None => line.trim(),
+            };
+            if line.is_empty() {
+                continue;
+            }
+            match Self::parse_line(line) {
+                Ok(instruction) => instructions.push((idx + 1, instruction)),
+                Err(kind) => errors.push(Error { line: idx + 1, kind }),
+            }
+        }
+        if errors.is_empty() {
+            Ok(instructions)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn parse_line(line: &str) -> Result<VmInstruction, ErrorKind> {
         let mut parts = line.split_whitespace();
-        let command = parts.next().expect("No command found");
-        match command {
+        let command = parts.next().expect("Line is not empty");
+        let mut argument = |argument| {
+            parts.next().ok_or_else(|| ErrorKind::MissingArgument { command: command.to_string(), argument })
+        };
+        Ok(match command {
             "push" => {
-                let segment = parts.next().expect("No segment found");
-                let index = parts.next().expect("No index found");
-                let segment = Segment::get_from_str(segment);
-                let index = index.parse().expect("Invalid index");
+                let segment = Segment::parse(argument("segment")?)?;
+                let index = segment.parse_index(argument("index")?)?;
                 VmInstruction::Push { segment, index }
             },
             "pop" => {
-                let segment = parts.next().expect("No segment found");
-                let index = parts.next().expect("No index found");
-                let segment = Segment::get_from_str(segment);
-                let index = index.parse().expect("Invalid index");
+                let segment = Segment::parse(argument("segment")?)?;
+                let index = segment.parse_index(argument("index")?)?;
+                if let Segment::Constant = segment {
+                    return Err(ErrorKind::PopConstant);
+                }
                 VmInstruction::Pop { segment, index }
             }
+            "function" | "call" => {
+                let name = argument("function name")?;
+                if !is_label(name) {
+                    return Err(ErrorKind::InvalidLabel(name.to_string()));
+                }
+                let count = argument("count")?;
+                // a call loads `count + 5` into A
+                let count = count.parse()
+                    .ok()
+                    .filter(|&x| x <= assembler::MAX_A_VALUE - 5)
+                    .ok_or_else(|| ErrorKind::InvalidCount(count.to_string()))?;
+                match command {
+                    "function" => VmInstruction::Function { name: name.to_string(), locals: count },
+                    _ => VmInstruction::Call { name: name.to_string(), arguments: count },
+                }
+            }
+            "return" => VmInstruction::Return,
+            "label" | "goto" | "if-goto" => {
+                let label = argument("label")?;
+                if !is_label(label) {
+                    return Err(ErrorKind::InvalidLabel(label.to_string()));
+                }
+                let label = label.to_string();
+                match command {
+                    "label" => VmInstruction::Label(label),
+                    "goto" => VmInstruction::Goto(label),
+                    _ => VmInstruction::IfGoto(label),
+                }
+            }
             "add" => VmInstruction::Add,
             "sub" => VmInstruction::Sub,
             "neg" => VmInstruction::Neg,
@@ -63,8 +244,14 @@ impl Context {
             "and" => VmInstruction::And,
             "or" => VmInstruction::Or,
             "not" => VmInstruction::Not,
-            _ => panic!("Invalid command: {}", command),
-        }
+            _ => return Err(ErrorKind::UnknownCommand(command.to_string())),
+        })
+    }
+
+    /// The assembly label of a VM label, which is local to its function,
+    /// or to the file outside of functions.
+    fn scoped_label(&self, label: &str) -> String {
+        format!("{}${}", self.function.as_deref().unwrap_or(&self.file_name), label)
     }
 
     fn translate_instruction(&mut self, instruction: VmInstruction) -> Vec<assembler::CodeLine> {
@@ -82,22 +269,38 @@ impl Context {
                         vec.extend(push(Comp::D));
                         vec
                     },
-                    _ => todo!(),
+                    Segment::Local | Segment::Argument | Segment::This | Segment::That | Segment::Temp => {
+                        // D = *(base + index)
+                        let mut vec = vec![CodeLine::constant(index), CodeLine::assign(Dest::D, Comp::A)];
+                        vec.extend(segment_base(segment));
+                        vec.extend([
+                            CodeLine::assign(Dest::AD, Comp::DPlusA),
+                            CodeLine::assign(Dest::D, Comp::M),
+                        ]);
+                        vec.extend(push(Comp::D));
+                        vec
+                    }
+                    Segment::Pointer | Segment::Static => {
+                        let mut vec = vec![self.segment_cell(segment, index), CodeLine::assign(Dest::D, Comp::M)];
+                        vec.extend(push(Comp::D));
+                        vec
+                    }
                 }
             }
             VmInstruction::Pop { segment, index } => {
                 match segment {
-                    Segment::Constant => panic!("Cannot pop to constant"),
-                    Segment::Local => {
-                        vec![
+                    Segment::Constant => unreachable!("Rejected by the parser"),
+                    Segment::Local | Segment::Argument | Segment::This | Segment::That | Segment::Temp => {
+                        let mut vec = vec![
                             // SP--
                             predefined_symbols::SP.into(),
                             CodeLine::assign(Dest::M, Comp::MMinusOne),
-                            // R13 = LCL + index
+                            // R13 = base + index
                             CodeLine::constant(index),
                             CodeLine::assign(Dest::D, Comp::A),
-                            predefined_symbols::LCL.into(),
-                            CodeLine::assign(Dest::A, Comp::M),
+                        ];
+                        vec.extend(segment_base(segment));
+                        vec.extend([
                             CodeLine::assign(Dest::AD, Comp::DPlusA),
                             predefined_symbols::R13.into(),
                             CodeLine::assign(Dest::M, Comp::D),
@@ -108,11 +311,50 @@ impl Context {
                             predefined_symbols::R13.into(),
                             CodeLine::assign(Dest::A, Comp::M),
                             CodeLine::assign(Dest::M, Comp::D),
-                        ]
+                        ]);
+                        vec
+                    }
+                    Segment::Pointer | Segment::Static => {
+                        let mut vec = Vec::with_capacity(7);
+                        vec.extend(pop(Dest::D));
+                        vec.extend([self.segment_cell(segment, index), CodeLine::assign(Dest::M, Comp::D)]);
+                        vec
                     }
-                    _ => todo!(),
                 }
             }
+            VmInstruction::Function { name, locals } => {
+                let mut vec = Vec::with_capacity(1 + 5 * usize::from(locals));
+                vec.push(CodeLine::Label(name.clone()));
+                for _ in 0..locals {
+                    vec.extend(push(Comp::Zero));
+                }
+                self.function = Some(name);
+                vec
+            }
+            VmInstruction::Call { name, arguments } => {
+                self.call(name, arguments)
+            }
+            VmInstruction::Return if self.shared_calls => {
+                vec![CodeLine::variable(RETURN_ROUTINE.to_string()), CodeLine::goto()]
+            }
+            VmInstruction::Return => {
+                ret()
+            }
+            VmInstruction::Label(label) => {
+                vec![CodeLine::Label(self.scoped_label(&label))]
+            }
+            VmInstruction::Goto(label) => {
+                vec![CodeLine::variable(self.scoped_label(&label)), CodeLine::goto()]
+            }
+            VmInstruction::IfGoto(label) => {
+                let mut vec = Vec::with_capacity(7);
+                vec.extend(pop(Dest::D));
+                vec.extend([
+                    CodeLine::variable(self.scoped_label(&label)),
+                    CodeLine::test(Dest::default(), Comp::D, Jump::JNE),
+                ]);
+                vec
+            }
             VmInstruction::Add => {
                 binary(Comp::DPlusA)
             }
@@ -142,6 +384,181 @@ impl Context {
             }
         }
     }
+
+    /// Pushes the return address and the caller's frame, repositions ARG and LCL and jumps.
+    fn call(&mut self, name: String, arguments: u16) -> Vec<CodeLine> {
+        use assembler::*;
+
+        let return_label = self.scoped_label(&format!("ret${}", self.label_index));
+        self.label_index += 1;
+        if self.shared_calls {
+            return vec![
+                CodeLine::variable(name),
+                CodeLine::assign(Dest::D, Comp::A),
+                predefined_symbols::R13.into(),
+                CodeLine::assign(Dest::M, Comp::D),
+                CodeLine::constant(arguments),
+                CodeLine::assign(Dest::D, Comp::A),
+                predefined_symbols::R14.into(),
+                CodeLine::assign(Dest::M, Comp::D),
+                CodeLine::variable(return_label.clone()),
+                CodeLine::assign(Dest::D, Comp::A),
+                predefined_symbols::R15.into(),
+                CodeLine::assign(Dest::M, Comp::D),
+                CodeLine::variable(CALL_ROUTINE.to_string()),
+                CodeLine::goto(),
+                CodeLine::Label(return_label),
+            ];
+        }
+        let mut vec = Vec::with_capacity(44);
+        vec.extend([CodeLine::variable(return_label.clone()), CodeLine::assign(Dest::D, Comp::A)]);
+        vec.extend(push(Comp::D));
+        vec.extend(push_frame());
+        vec.extend([
+            // ARG = SP - 5 - arguments
+            predefined_symbols::SP.into(),
+            CodeLine::assign(Dest::D, Comp::M),
+            CodeLine::constant(arguments + 5),
+            CodeLine::assign(Dest::D, Comp::DMinusA),
+            predefined_symbols::ARG.into(),
+            CodeLine::assign(Dest::M, Comp::D),
+            // LCL = SP
+            predefined_symbols::SP.into(),
+            CodeLine::assign(Dest::D, Comp::M),
+            predefined_symbols::LCL.into(),
+            CodeLine::assign(Dest::M, Comp::D),
+            CodeLine::variable(name),
+            CodeLine::goto(),
+            CodeLine::Label(return_label),
+        ]);
+        vec
+    }
+
+    /// The A-instruction addressing a `pointer` or `static` cell, which need no base address.
+    fn segment_cell(&self, segment: Segment, index: u16) -> CodeLine {
+        use assembler::*;
+
+        match (segment, index) {
+            (Segment::Pointer, 0) => predefined_symbols::THIS.into(),
+            (Segment::Pointer, _) => predefined_symbols::THAT.into(),
+            (Segment::Static, _) => CodeLine::variable(format!("{}.{}", self.file_name, index)),
+            _ => unreachable!("{:?} is addressed through its base", segment),
+        }
+    }
+}
+
+/// Loads the base address of a segment into A.
+fn segment_base(segment: Segment) -> Vec<CodeLine> {
+    use assembler::*;
+
+    let pointer = match segment {
+        Segment::Local => predefined_symbols::LCL,
+        Segment::Argument => predefined_symbols::ARG,
+        Segment::This => predefined_symbols::THIS,
+        Segment::That => predefined_symbols::THAT,
+        Segment::Temp => return vec![CodeLine::constant(TEMP_BASE)],
+        _ => unreachable!("{:?} has no base address", segment),
+    };
+    vec![pointer.into(), CodeLine::assign(Dest::A, Comp::M)]
+}
+
+/// Pushes the caller's LCL, ARG, THIS and THAT.
+fn push_frame() -> Vec<CodeLine> {
+    use assembler::*;
+
+    let mut vec = Vec::with_capacity(28);
+    for pointer in [predefined_symbols::LCL, predefined_symbols::ARG, predefined_symbols::THIS, predefined_symbols::THAT] {
+        vec.extend([pointer.into(), CodeLine::assign(Dest::D, Comp::M)]);
+        vec.extend(push(Comp::D));
+    }
+    vec
+}
+
+/// The shared part of a call with shared calls: pushes the return address in R15 and
+/// the caller's frame, sets ARG from the argument count in R14 and jumps to the target in R13.
+fn call_routine() -> Vec<CodeLine> {
+    use assembler::*;
+
+    let mut vec = vec![
+        CodeLine::Label(CALL_ROUTINE.to_string()),
+        predefined_symbols::R15.into(),
+        CodeLine::assign(Dest::D, Comp::M),
+    ];
+    vec.extend(push(Comp::D));
+    vec.extend(push_frame());
+    vec.extend([
+        // ARG = SP - 5 - R14
+        predefined_symbols::R14.into(),
+        CodeLine::assign(Dest::D, Comp::M),
+        CodeLine::constant(5),
+        CodeLine::assign(Dest::D, Comp::DPlusA),
+        predefined_symbols::SP.into(),
+        CodeLine::assign(Dest::D, Comp::MMinusD),
+        predefined_symbols::ARG.into(),
+        CodeLine::assign(Dest::M, Comp::D),
+        // LCL = SP
+        predefined_symbols::SP.into(),
+        CodeLine::assign(Dest::D, Comp::M),
+        predefined_symbols::LCL.into(),
+        CodeLine::assign(Dest::M, Comp::D),
+        predefined_symbols::R13.into(),
+        CodeLine::assign(Dest::A, Comp::M),
+        CodeLine::goto(),
+    ]);
+    vec
+}
+
+/// Moves the return value to the caller's stack, restores its frame and jumps back.
+fn ret() -> Vec<CodeLine> {
+    use assembler::*;
+
+    let mut vec = vec![
+        // R13 = frame = LCL
+        predefined_symbols::LCL.into(),
+        CodeLine::assign(Dest::D, Comp::M),
+        predefined_symbols::R13.into(),
+        CodeLine::assign(Dest::M, Comp::D),
+        // R14 = return address = *(frame - 5), before the return value may overwrite it
+        CodeLine::constant(5),
+        CodeLine::assign(Dest::A, Comp::DMinusA),
+        CodeLine::assign(Dest::D, Comp::M),
+        predefined_symbols::R14.into(),
+        CodeLine::assign(Dest::M, Comp::D),
+    ];
+    // *ARG = pop()
+    vec.extend(pop(Dest::D));
+    vec.extend([
+        predefined_symbols::ARG.into(),
+        CodeLine::assign(Dest::A, Comp::M),
+        CodeLine::assign(Dest::M, Comp::D),
+        // SP = ARG + 1
+        predefined_symbols::ARG.into(),
+        CodeLine::assign(Dest::D, Comp::MPlusOne),
+        predefined_symbols::SP.into(),
+        CodeLine::assign(Dest::M, Comp::D),
+    ]);
+    // THAT, THIS, ARG, LCL = *(--frame)
+    for pointer in [predefined_symbols::THAT, predefined_symbols::THIS, predefined_symbols::ARG, predefined_symbols::LCL] {
+        vec.extend([
+            predefined_symbols::R13.into(),
+            CodeLine::assign(Dest::AM, Comp::MMinusOne),
+            CodeLine::assign(Dest::D, Comp::M),
+            pointer.into(),
+            CodeLine::assign(Dest::M, Comp::D),
+        ]);
+    }
+    vec.extend([
+        predefined_symbols::R14.into(),
+        CodeLine::assign(Dest::A, Comp::M),
+        CodeLine::goto(),
+    ]);
+    vec
+}
+
+/// Checks the VM spec's label syntax: letters, digits, `_`, `.` and `:`, not starting with a digit.
+fn is_label(s: &str) -> bool {
+    let is_label_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':');
+    !s.starts_with(|c: char| c.is_ascii_digit()) && !s.is_empty() && s.chars().all(is_label_char)
 }
 
 fn unary(comp: assembler::Comp) -> Vec<assembler::CodeLine> {
@@ -205,11 +622,11 @@ fn comparison(jump: assembler::Jump, label_index: &mut u16) -> Vec<CodeLine> {
         CodeLine::assign(Dest::M, Comp::Zero),
         CodeLine::variable(label2.clone()),
         CodeLine::goto(),
-        CodeLine::Label(label1.into()),
+        CodeLine::Label(label1),
         predefined_symbols::SP.into(),
         CodeLine::assign(Dest::A, Comp::M),
         CodeLine::assign(Dest::M, Comp::NegOne),
-        CodeLine::Label(label2.into()),
+        CodeLine::Label(label2),
         predefined_symbols::SP.into(),
         CodeLine::assign(Dest::M, Comp::MPlusOne),
     ]);
@@ -225,6 +642,18 @@ enum VmInstruction {
         segment: Segment,
         index: u16,
     },
+    Function {
+        name: String,
+        locals: u16,
+    },
+    Call {
+        name: String,
+        arguments: u16,
+    },
+    Return,
+    Label(String),
+    Goto(String),
+    IfGoto(String),
     Add,
     Eq,
     Lt,
@@ -246,13 +675,25 @@ enum Segment {
     This,
     That,
     Temp,
+    Pointer,
 }
 
 impl Segment {
-    fn get_from_str(s: &str) -> Self {
-        match FromStr::from_str(s) {
-            Ok(x) => x,
-            Err(e) => panic!("Invalid segment: {}: {:?}", s, e),
+    fn parse(s: &str) -> Result<Self, ErrorKind> {
+        FromStr::from_str(s).map_err(|_| ErrorKind::UnknownSegment(s.to_string()))
+    }
+
+    /// Parses the index of a push or pop, checking the size of the fixed segments.
+    fn parse_index(&self, s: &str) -> Result<u16, ErrorKind> {
+        let index = s.parse().map_err(|_| ErrorKind::InvalidIndex(s.to_string()))?;
+        let (segment, size) = match self {
+            Segment::Temp => ("temp", 8),
+            Segment::Pointer => ("pointer", 2),
+            _ => return Ok(index),
+        };
+        if index >= size {
+            return Err(ErrorKind::IndexOutOfRange { segment, index, size });
         }
+        Ok(index)
     }
 }
@@ -0,0 +1,58 @@
+/// An error in a VM source line, `line` is 1-based, or 0 for errors about a file as a whole.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnknownCommand(String),
+    /// A command without one of its arguments, holds what is missing, e.g. `segment`.
+    MissingArgument { command: String, argument: &'static str },
+    UnknownSegment(String),
+    InvalidIndex(String),
+    /// The number of locals of a `function` or of arguments of a `call`.
+    InvalidCount(String),
+    IndexOutOfRange { segment: &'static str, index: u16, size: u16 },
+    PopConstant,
+    InvalidLabel(String),
+    DuplicateLabel(String),
+    UndefinedLabel(String),
+    /// A `call` to a function that none of the translated files defines.
+    UndefinedFunction(String),
+    /// `Sys.vm` without the `Sys.init` function the bootstrap code calls.
+    MissingSysInit,
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ErrorKind::UnknownCommand(command) => write!(f, "unknown command `{}`", command),
+            ErrorKind::MissingArgument { command, argument } => write!(f, "`{}` is missing its {}", command, argument),
+            ErrorKind::UnknownSegment(segment) => write!(f, "unknown segment `{}`", segment),
+            ErrorKind::InvalidIndex(index) => write!(f, "invalid index `{}`", index),
+            ErrorKind::InvalidCount(count) => write!(f, "invalid count `{}`", count),
+            ErrorKind::IndexOutOfRange { segment, index, size } => {
+                write!(f, "index {} is out of range, `{}` has {} cells", index, segment, size)
+            }
+            ErrorKind::PopConstant => write!(f, "cannot pop to `constant`"),
+            ErrorKind::InvalidLabel(label) => write!(f, "invalid label `{}`", label),
+            ErrorKind::DuplicateLabel(label) => write!(f, "label `{}` is already defined", label),
+            ErrorKind::UndefinedLabel(label) => write!(f, "jump to undefined label `{}`", label),
+            ErrorKind::UndefinedFunction(name) => write!(f, "call to undefined function `{}`", name),
+            ErrorKind::MissingSysInit => write!(f, "`Sys.init` is not defined, the bootstrap code calls it"),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.line {
+            0 => write!(f, "{}", self.kind),
+            line => write!(f, "{}: {}", line, self.kind),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
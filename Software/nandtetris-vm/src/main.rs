@@ -1,24 +1,104 @@
 pub mod core;
+pub mod error;
 
 
 use std::env;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use core::Context;
 
+const USAGE: &str = "Usage: nandtetris-vm [--optimize] [--no-bootstrap] [--shared-calls] <file.vm | directory>";
+
+#[derive(Debug)]
+struct Args {
+    /// A `.vm` file or a directory of them.
+    path: PathBuf,
+    optimize: bool,
+    bootstrap: bool,
+    shared_calls: bool,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut path = None;
+        let mut result = Args { path: PathBuf::new(), optimize: false, bootstrap: true, shared_calls: false };
+        for arg in args {
+            match arg.as_str() {
+                "-O" | "--optimize" => result.optimize = true,
+                "--no-bootstrap" => result.bootstrap = false,
+                "--shared-calls" => result.shared_calls = true,
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ if path.is_some() => return Err(format!("Only one file or directory can be translated: {}", arg)),
+                _ if arg.ends_with(".vm") || Path::new(&arg).is_dir() => path = Some(PathBuf::from(arg)),
+                _ => return Err(format!("File must have .vm extension: {}", arg)),
+            }
+        }
+        result.path = path.ok_or("No file name provided")?;
+        Ok(result)
+    }
+
+    /// The files to translate and the output file: a directory is translated to
+    /// `<dir>/<dir>.asm`, a file `<name>.vm` to `<name>.asm`.
+    fn files(&self) -> Result<(Vec<PathBuf>, PathBuf), String> {
+        if !self.path.is_dir() {
+            return Ok((vec![self.path.clone()], self.path.with_extension("asm")));
+        }
+        let read_error = |e: std::io::Error| format!("Could not read directory: {}", e);
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(&self.path).map_err(read_error)? {
+            let path = entry.map_err(read_error)?.path();
+            if path.extension().is_some_and(|x| x == "vm") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        if paths.is_empty() {
+            return Err("Directory has no .vm files".to_string());
+        }
+        let name = self.path.canonicalize().map_err(|e| format!("Could not resolve directory: {}", e))?;
+        let name = name.file_name().map_or("out".into(), |x| x.to_string_lossy());
+        let out_file = self.path.join(format!("{}.asm", name));
+        Ok((paths, out_file))
+    }
+}
+
 fn main() {
-    let file_name = env::args().nth(1).expect("No file name provided");
-    assert!(file_name.ends_with(".vm"), "File name must end with .vm");
-    let file = std::fs::read_to_string(&file_name).expect("Could not read file");
-    let instructions = Context::default().translate(&file);
-    let out_file = file_name.replace(".vm", ".asm");
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        std::process::exit(2);
+    });
+    let (paths, out_file) = args.files().unwrap_or_else(|e| {
+        eprintln!("{}: {}", args.path.display(), e);
+        std::process::exit(1);
+    });
+    let sources = paths.iter().map(|path| {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let code = std::fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("{}: Could not read file: {}", path.display(), e);
+            std::process::exit(1);
+        });
+        (stem, code)
+    }).collect::<Vec<_>>();
+    let files = sources.iter().map(|(stem, code)| (stem.as_str(), code.as_str())).collect::<Vec<_>>();
+    let mut context = Context::default();
+    context.optimize = args.optimize;
+    let code = context.translate_files(&files, args.bootstrap, args.shared_calls).unwrap_or_else(|errors| {
+        for (stem, error) in &errors {
+            let path = paths.iter().find(|x| x.file_stem().is_some_and(|x| x == stem.as_str())).expect("Error in a translated file");
+            match error.line {
+                0 => eprintln!("{}: {}", path.display(), error),
+                _ => eprintln!("{}:{}", path.display(), error),
+            }
+        }
+        std::process::exit(1);
+    });
     let file = std::fs::File::create(&out_file).expect("Could not create file");
     let mut writer = std::io::BufWriter::new(file);
-    for instruction in instructions {
+    for instruction in code {
         writeln!(writer, "{}", instruction).expect("Could not write to file");
     }
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,13 +124,37 @@ mod tests {
             let (input, expected) = get_test_files!($name);
             let expected = expected.trim().lines().collect::<Vec<_>>();
 
-            let instructions = Context::default().translate(input);
+            let mut context = Context::default();
+            context.file_name = $name.to_string();
+            let instructions = context.translate(input).unwrap();
             let instructions = instructions.iter().collect::<Vec<_>>();
 
             assert_eq!(instructions, expected);
         };
     }
 
+    #[test]
+    fn test_parses_args() {
+        let args = ["-O", "--shared-calls", "Main.vm"].map(String::from);
+        let args = Args::parse(args.into_iter()).unwrap();
+        assert!(args.optimize && args.shared_calls && args.bootstrap);
+        assert_eq!(args.files().unwrap(), (vec![PathBuf::from("Main.vm")], PathBuf::from("Main.asm")));
+
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/FibonacciElement");
+        let args = ["--no-bootstrap".to_string(), dir.to_string_lossy().into_owned()];
+        let args = Args::parse(args.into_iter()).unwrap();
+        assert!(!args.bootstrap);
+        let (paths, out_file) = args.files().unwrap();
+        assert_eq!(paths, [dir.join("Main.vm"), dir.join("Sys.vm")]);
+        assert_eq!(out_file, dir.join("FibonacciElement.asm"));
+
+        let parse = |args: &[&str]| Args::parse(args.iter().map(|x| x.to_string())).unwrap_err();
+        assert_eq!(parse(&["--nope", "Main.vm"]), "Unknown option --nope");
+        assert_eq!(parse(&["Main.vm", "Sys.vm"]), "Only one file or directory can be translated: Sys.vm");
+        assert_eq!(parse(&["Main.asm"]), "File must have .vm extension: Main.asm");
+        assert_eq!(parse(&["-O"]), "No file name provided");
+    }
+
     #[test]
     fn test_simple_add() {
         test_program!("SimpleAdd");
@@ -61,8 +165,176 @@ mod tests {
         test_program!("StackTest");
     }
 
+    #[test]
+    fn test_assemble_in_process() {
+        let (input, expected) = get_test_files!("StackTest");
+        let expected = nandtetris_shared::assembler::assemble(expected).unwrap();
+
+        let code = Context::default().translate_code(input).unwrap();
+        let instructions = nandtetris_shared::assembler::Context::default().assemble_lines(code).unwrap();
+        let instructions = instructions.into_iter().map(u16::from).collect::<Vec<_>>();
+
+        assert_eq!(instructions, expected);
+    }
+
+    #[test]
+    fn test_optimize() {
+        let (input, _) = get_test_files!("StackTest");
+        let plain = Context::default().translate_code(input).unwrap();
+        let mut context = Context::default();
+        context.optimize = true;
+        let optimized = context.translate_code(input).unwrap();
+        assert!(optimized.len() * 10 < plain.len() * 8, "{} of {} lines left", optimized.len(), plain.len());
+        nandtetris_shared::assembler::Context::default().assemble_lines(optimized).unwrap();
+    }
+
     #[test]
     fn test_basic_test() {
         test_program!("BasicTest");
     }
+
+    #[test]
+    fn test_pointer_test() {
+        test_program!("PointerTest");
+    }
+
+    #[test]
+    fn test_static_test() {
+        test_program!("StaticTest");
+    }
+
+    #[test]
+    fn test_basic_loop() {
+        test_program!("BasicLoop");
+    }
+
+    #[test]
+    fn test_fibonacci_series() {
+        test_program!("FibonacciSeries");
+    }
+
+    #[test]
+    fn test_simple_function() {
+        test_program!("SimpleFunction");
+    }
+
+    #[test]
+    fn test_nested_call() {
+        test_program!("NestedCall");
+    }
+
+    macro_rules! test_directory {
+        ($name:literal, $($file:literal),+) => {
+            let files = [$(
+                ($file, include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/", $name, "/", $file, ".vm"))),
+            )+];
+            let expected = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/", $name, "/", $name, ".asm"));
+            let expected = expected.trim().lines().collect::<Vec<_>>();
+
+            let code = Context::default().translate_files(&files, true, false).unwrap();
+            let instructions = code.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+            assert_eq!(instructions, expected);
+        };
+    }
+
+    #[test]
+    fn test_fibonacci_element() {
+        test_directory!("FibonacciElement", "Main", "Sys");
+    }
+
+    #[test]
+    fn test_statics_test() {
+        test_directory!("StaticsTest", "Class1", "Class2", "Sys");
+    }
+
+    #[test]
+    fn test_shared_calls() {
+        let files = [
+            ("Main", include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/FibonacciElement/Main.vm"))),
+            ("Sys", include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/FibonacciElement/Sys.vm"))),
+        ];
+        let expected = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/FibonacciElement/FibonacciElementShared.asm"));
+        let expected = expected.trim().lines().collect::<Vec<_>>();
+
+        let mut context = Context::default();
+        let code = context.translate_files(&files, true, true).unwrap();
+        let instructions = code.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(instructions, expected);
+        // single files have no routines to jump to
+        let call = context.translate_code("call Main.fibonacci 1\n").unwrap();
+        assert!(call.iter().all(|x| !x.to_string().contains("$$call")));
+
+        let inlined = Context::default().translate_files(&files, true, false).unwrap();
+        assert!(code.len() * 4 < inlined.len() * 3, "{} of {} lines left", code.len(), inlined.len());
+        nandtetris_shared::assembler::Context::default().assemble_lines(code).unwrap();
+    }
+
+    #[test]
+    fn test_bootstrap_needs_sys() {
+        let files = [("Main", "function Main.main 0\npush constant 0\nreturn\n")];
+        let code = Context::default().translate_files(&files, true, false).unwrap();
+        assert_eq!(code[0].to_string(), "(Main.main)");
+
+        let files = [("Sys", "function Sys.init 0\nlabel WHILE\ngoto WHILE\n")];
+        let code = Context::default().translate_files(&files, false, false).unwrap();
+        assert_eq!(code[0].to_string(), "(Sys.init)");
+        let code = Context::default().translate_files(&files, true, false).unwrap();
+        assert_eq!(code[..4].iter().map(|x| x.to_string()).collect::<Vec<_>>(), ["@256", "D=A", "@SP", "M=D"]);
+
+        let files = [("Main", "push static 0\n"), ("Sys", "pop static 1\npop static 2\nfoo\n")];
+        let errors = Context::default().translate_files(&files, true, false).unwrap_err();
+        let errors = errors.iter().map(|(file, error)| format!("{}:{}", file, error)).collect::<Vec<_>>();
+        assert_eq!(errors, ["Sys:`Sys.init` is not defined, the bootstrap code calls it", "Sys:3: unknown command `foo`"]);
+    }
+
+    #[test]
+    fn test_return_labels_do_not_clash() {
+        let input = "function Main.main 0\nlabel ret.1\ncall Main.main 0\ngoto ret.1\n";
+        let code = Context::default().translate_code(input).unwrap();
+        nandtetris_shared::assembler::Context::default().assemble_lines(code).unwrap();
+    }
+
+    #[test]
+    fn test_reports_undefined_functions() {
+        let files = [
+            ("Main", "function Main.main 0\ncall Math.multiply 2\ncall Main.main 0\nreturn\n"),
+            ("Sys", "function Sys.main 0\ncall Main.main 0\ncall Sys.halt 0\n"),
+        ];
+        let errors = Context::default().translate_files(&files, true, false).unwrap_err();
+        let errors = errors.iter().map(|(file, error)| format!("{}:{}", file, error)).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "Main:2: call to undefined function `Math.multiply`",
+            "Sys:`Sys.init` is not defined, the bootstrap code calls it",
+            "Sys:3: call to undefined function `Sys.halt`",
+        ]);
+        assert_eq!(Context::default().translate_files(&files, false, false).unwrap_err().len(), 2);
+    }
+
+    #[test]
+    fn test_reports_errors() {
+        let input = "push local\npop constant 1\npush temp 8\nlabel 1ST\nlabel LOOP\nlabel LOOP\ngoto END\njump LOOP\n";
+        let errors = Context::default().translate(input).unwrap_err();
+        let errors = errors.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, [
+            "1: `push` is missing its index",
+            "2: cannot pop to `constant`",
+            "3: index 8 is out of range, `temp` has 8 cells",
+            "4: invalid label `1ST`",
+            "8: unknown command `jump`",
+        ]);
+
+        let errors = Context::default().translate("label LOOP\nlabel LOOP\nif-goto END\n").unwrap_err();
+        let errors = errors.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, ["2: label `LOOP` is already defined", "3: jump to undefined label `END`"]);
+
+        // labels are local to their function
+        let input = "function A.f 0\nlabel LOOP\nfunction A.g 1\ngoto LOOP\ncall A.f x\n";
+        let errors = Context::default().translate(input).unwrap_err();
+        let errors = errors.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, ["5: invalid count `x`"]);
+        let errors = Context::default().translate(&input.replace(" x", " 0")).unwrap_err();
+        let errors = errors.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors, ["4: jump to undefined label `LOOP`"]);
+    }
 }